@@ -0,0 +1,33 @@
+//! Times `Environment::step` with a random agent on the terminal front-end's
+//! board, restarting whenever an episode ends. Run with `cargo bench`.
+use rand::prelude::*;
+use rand_pcg::Pcg64;
+use snake::environment::{Action, Environment};
+use snake::level::{self, Level};
+use std::time::Instant;
+
+const STEPS: u32 = 1_000_000;
+
+fn main() {
+    let level = Level::parse(level::TERMINAL).unwrap();
+    let mut environment = Environment::new(level.start(0));
+    let mut rng = Pcg64::seed_from_u64(0);
+    let mut episodes = 1;
+    environment.reset(0);
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        let action = *Action::RELATIVE.choose(&mut rng).unwrap();
+        if environment.step(action).done {
+            environment.reset(episodes);
+            episodes += 1;
+        }
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{} steps over {} episodes: {:.0} steps/s ({:.0} ns/step)",
+        STEPS,
+        episodes,
+        STEPS as f64 / elapsed.as_secs_f64(),
+        elapsed.as_nanos() as f64 / STEPS as f64
+    );
+}
@@ -0,0 +1,57 @@
+//! Times collision checks and apple spawning on a 1000x1000 board that the
+//! snake almost fills. Run with `cargo bench`.
+use snake::{Direction, Game};
+use std::time::Instant;
+
+const SIZE: i32 = 1000;
+
+/// A snake snaking back and forth over every row but the last, with its head
+/// dropped into the bottom right corner.
+fn nearly_full_game() -> Game {
+    let mut body = vec![(SIZE - 1, SIZE - 1)];
+    for y in (0..SIZE - 1).rev() {
+        if y % 2 == 0 {
+            body.extend((0..SIZE).rev().map(|x| (y, x)));
+        } else {
+            body.extend((0..SIZE).map(|x| (y, x)));
+        }
+    }
+    Game::new(SIZE as usize, SIZE as usize, &body, 0)
+}
+
+fn report(name: &str, iterations: u32, start: Instant) {
+    let elapsed = start.elapsed();
+    println!(
+        "{:<40} {:>10.0} ns/iter ({} iterations)",
+        name,
+        elapsed.as_nanos() as f64 / iterations as f64,
+        iterations
+    );
+}
+
+fn main() {
+    let start = Instant::now();
+    let mut game = nearly_full_game();
+    report("building a game around a 999001 cell snake", 1, start);
+
+    let start = Instant::now();
+    game.rebuild_occupancy();
+    report("rebuilding the occupancy grid", 1, start);
+
+    let start = Instant::now();
+    let mut spawned = 0;
+    while game.add_new_apple().is_some() {
+        spawned += 1;
+    }
+    report("spawning apples until the board is full", spawned, start);
+
+    let mut game = nearly_full_game();
+    game.snake.turn(Direction::Left);
+    let ticks = SIZE as u32 - 1;
+    let start = Instant::now();
+    for _ in 0..ticks {
+        game.advance();
+    }
+    report("advancing along the last free row", ticks, start);
+    assert!(!game.over);
+}
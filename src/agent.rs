@@ -0,0 +1,205 @@
+//! Things that can play a game of snake, so they can be compared.
+//!
+//! An agent is asked for an `Action` every tick. Built-in agents are looked
+//! up by name with `by_name`, and any program that speaks the protocol
+//! described on `Bot` can play too.
+use crate::apple::Apple;
+use crate::autopilot;
+use crate::environment::Action;
+use crate::replay::input_from_glyph;
+use crate::snake::Direction;
+use crate::Game;
+use rand::prelude::*;
+use rand_pcg::Pcg64;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// The built-in agents `by_name` knows, besides `bot:` ones.
+pub const NAMES: [&str; 2] = ["autopilot", "random"];
+
+/// How long a `Bot` gets to answer each tick.
+const ANSWER_TIMEOUT: Duration = Duration::from_millis(100);
+
+pub trait Agent {
+    /// Gets ready for a new game, whose apples are placed from `seed`.
+    fn reset(&mut self, _seed: u64) {}
+
+    /// The move to make on `game`'s next tick.
+    fn act(&mut self, game: &Game) -> Action;
+}
+
+/// The agent called `name`: one of `NAMES`, or `bot:` followed by the
+/// command line of a program to run as a `Bot`.
+pub fn by_name(name: &str) -> Result<Box<dyn Agent>, String> {
+    if let Some(command) = name.strip_prefix("bot:") {
+        return Ok(Box::new(Bot::spawn(command)?));
+    }
+    match name {
+        "autopilot" => Ok(Box::new(Autopilot)),
+        "random" => Ok(Box::new(Random::new())),
+        _ => Err(format!(
+            "Unknown agent {}, expected one of {} or bot:<command>",
+            name,
+            NAMES.join(", ")
+        )),
+    }
+}
+
+/// The `autopilot` module's pathfinding.
+pub struct Autopilot;
+
+impl Agent for Autopilot {
+    fn act(&mut self, game: &Game) -> Action {
+        Action::Towards(autopilot::choose(game))
+    }
+}
+
+/// Turns left, right or carries straight on at random, with the choices
+/// seeded from each game's seed.
+pub struct Random {
+    rng: Pcg64,
+}
+
+impl Random {
+    pub fn new() -> Random {
+        Random {
+            rng: Pcg64::seed_from_u64(0),
+        }
+    }
+}
+
+impl Default for Random {
+    fn default() -> Random {
+        Random::new()
+    }
+}
+
+impl Agent for Random {
+    fn reset(&mut self, seed: u64) {
+        self.rng = Pcg64::seed_from_u64(seed);
+    }
+
+    fn act(&mut self, _game: &Game) -> Action {
+        *Action::RELATIVE.choose(&mut self.rng).unwrap()
+    }
+}
+
+/// What a `Bot` is sent each tick.
+#[derive(Serialize)]
+struct View<'a> {
+    width: usize,
+    height: usize,
+    ticks: u32,
+    score: u32,
+    direction: Direction,
+    wraps: bool,
+    /// Head first, as `[y, x]` pairs.
+    snake: &'a VecDeque<(i32, i32)>,
+    apples: Vec<&'a Apple>,
+    obstacles: Vec<(i32, i32)>,
+}
+
+/// An agent running as a separate program.
+///
+/// Each tick the program is sent the game as a line of JSON on its standard
+/// input, with the board's `width` and `height`, the `ticks` and `score` so
+/// far, the snake's `direction`, whether it `wraps`, the `snake` head first
+/// and the `apples` and `obstacles`. Cells are `[y, x]` pairs. It answers
+/// with a line holding `U`, `R`, `D` or `L` to head that way, or `.` to
+/// carry straight on, the same glyphs replays use. A new game starts with
+/// `ticks` back at 0.
+///
+/// A program that takes longer than `ANSWER_TIMEOUT` to answer, stops
+/// answering or answers with anything else carries straight on. An answer
+/// that turns up late is thrown away rather than used for the next tick.
+pub struct Bot {
+    child: Child,
+    input: ChildStdin,
+    /// The lines the program writes, read on a thread of their own so a
+    /// program that doesn't answer can't hold the game up.
+    answers: Receiver<String>,
+}
+
+impl Bot {
+    /// Starts `command`, a program followed by its arguments separated by
+    /// spaces.
+    pub fn spawn(command: &str) -> Result<Bot, String> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| String::from("bot: expects a command"))?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|error| format!("Couldn't start {}: {}", program, error))?;
+        let input = child.stdin.take().unwrap();
+        let output = BufReader::new(child.stdout.take().unwrap());
+        let (sender, answers) = mpsc::channel();
+        thread::spawn(move || {
+            for line in output.lines() {
+                let sent = line.map(|line| sender.send(line));
+                if !matches!(sent, Ok(Ok(()))) {
+                    return;
+                }
+            }
+        });
+        Ok(Bot {
+            child,
+            input,
+            answers,
+        })
+    }
+
+    fn ask(&mut self, game: &Game) -> Option<Action> {
+        let mut apples: Vec<&Apple> = game.apples.values().collect();
+        apples.sort_by_key(|apple| apple.location);
+        let mut obstacles: Vec<(i32, i32)> = game
+            .obstacles
+            .iter()
+            .map(|obstacle| obstacle.location)
+            .collect();
+        obstacles.sort_unstable();
+        let view = View {
+            width: game.width,
+            height: game.height,
+            ticks: game.ticks,
+            score: game.score,
+            direction: game.snake.direction,
+            wraps: game.snake.wraps,
+            snake: &game.snake.body,
+            apples,
+            obstacles,
+        };
+        while self.answers.try_recv().is_ok() {}
+        serde_json::to_writer(&mut self.input, &view).ok()?;
+        writeln!(self.input).ok()?;
+        self.input.flush().ok()?;
+        let line = self.answers.recv_timeout(ANSWER_TIMEOUT).ok()?;
+        let mut glyphs = line.trim().chars();
+        match (glyphs.next().and_then(input_from_glyph), glyphs.next()) {
+            (Some(Some(direction)), None) => Some(Action::Towards(direction)),
+            (Some(None), None) => Some(Action::Straight),
+            _ => None,
+        }
+    }
+}
+
+impl Agent for Bot {
+    fn act(&mut self, game: &Game) -> Action {
+        self.ask(game).unwrap_or(Action::Straight)
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -1,4 +1,159 @@
-#[derive(PartialEq, Eq, Hash)]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+
+/// What eating an apple does to the snake.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum AppleKind {
+    /// One point and one segment of growth.
+    Normal,
+    /// Five points and one segment of growth.
+    Golden,
+    /// One point and three segments of growth.
+    Feast,
+    /// One point, and takes two segments off the tail.
+    Shrinking,
+    /// One point and one segment of growth, and speeds the game up for a
+    /// while.
+    Speed,
+    /// Kills the snake.
+    Poison,
+}
+
+/// Every kind of apple, with how often it turns up out of 100 when special
+/// apples are on.
+const KIND_WEIGHTS: [(AppleKind, u32); 6] = [
+    (AppleKind::Normal, 70),
+    (AppleKind::Golden, 8),
+    (AppleKind::Feast, 8),
+    (AppleKind::Shrinking, 6),
+    (AppleKind::Speed, 5),
+    (AppleKind::Poison, 3),
+];
+
+impl AppleKind {
+    /// Picks a kind at random, mostly `Normal`.
+    pub fn random<R: rand::Rng + ?Sized>(rng: &mut R) -> AppleKind {
+        let total: u32 = KIND_WEIGHTS.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng.gen_range(0, total);
+        for (kind, weight) in KIND_WEIGHTS.iter() {
+            if roll < *weight {
+                return *kind;
+            }
+            roll -= weight;
+        }
+        AppleKind::Normal
+    }
+
+    pub fn points(self) -> u32 {
+        match self {
+            AppleKind::Golden => 5,
+            AppleKind::Poison => 0,
+            _ => 1,
+        }
+    }
+
+    /// How many segments the snake grows by.
+    pub fn growth(self) -> u32 {
+        match self {
+            AppleKind::Normal | AppleKind::Golden | AppleKind::Speed => 1,
+            AppleKind::Feast => 3,
+            AppleKind::Shrinking | AppleKind::Poison => 0,
+        }
+    }
+
+    /// How many segments come off the tail.
+    pub fn shrinkage(self) -> usize {
+        match self {
+            AppleKind::Shrinking => 2,
+            _ => 0,
+        }
+    }
+
+    /// How many ticks the game runs faster for.
+    pub fn speed_boost(self) -> u32 {
+        match self {
+            AppleKind::Speed => 25,
+            _ => 0,
+        }
+    }
+
+    /// The glyph the print renderer and level files use for this kind.
+    pub fn glyph(self) -> char {
+        match self {
+            AppleKind::Normal => 'O',
+            AppleKind::Golden => '$',
+            AppleKind::Feast => '@',
+            AppleKind::Shrinking => 'o',
+            AppleKind::Speed => '!',
+            AppleKind::Poison => 'X',
+        }
+    }
+
+    pub fn from_glyph(glyph: char) -> Option<AppleKind> {
+        KIND_WEIGHTS
+            .iter()
+            .map(|(kind, _)| *kind)
+            .find(|kind| kind.glyph() == glyph)
+    }
+}
+
+/// An apple waiting to be eaten, at `(y, x)` on the board.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct Apple {
     pub location: (i32, i32),
+    pub kind: AppleKind,
+    /// Ticks left before the apple disappears, or `None` if it stays until
+    /// it's eaten.
+    pub lifetime: Option<u32>,
+}
+
+impl Apple {
+    /// A `Normal` apple at `location` that never expires.
+    pub fn new(location: (i32, i32)) -> Apple {
+        Apple {
+            location,
+            kind: AppleKind::Normal,
+            lifetime: None,
+        }
+    }
+
+    /// The glyph the print renderer draws: the kind's glyph, or a countdown
+    /// digit once fewer than ten ticks are left.
+    pub fn glyph(&self) -> char {
+        match self.lifetime {
+            Some(ticks) if ticks < 10 => std::char::from_digit(ticks, 10).unwrap(),
+            _ => self.kind.glyph(),
+        }
+    }
+}
+
+/// The apples on the board, keyed by the cell each one is on, since there's
+/// at most one per cell.
+pub type Apples = HashMap<(i32, i32), Apple>;
+
+/// Saves `Apples` as a list sorted by location, since JSON keys have to be
+/// strings, and loads them back from one.
+pub(crate) mod by_location {
+    use super::*;
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer>(apples: &Apples, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut list: Vec<&Apple> = apples.values().collect();
+        list.sort_by_key(|apple| apple.location);
+        list.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Apples, D::Error> {
+        let list = Vec::<Apple>::deserialize(deserializer)?;
+        let mut apples = Apples::new();
+        for apple in list {
+            if apples.insert(apple.location, apple).is_some() {
+                return Err(D::Error::custom(format!(
+                    "two apples are at {:?}",
+                    apple.location
+                )));
+            }
+        }
+        Ok(apples)
+    }
 }
@@ -0,0 +1,214 @@
+//! A snake that steers itself, as a baseline for agents to beat.
+//!
+//! Each tick the autopilot finds the shortest route to the nearest apple with
+//! a breadth-first search, then plays the route out on a copy of the game to
+//! check the snake could still reach its own tail once it got there. A snake
+//! that can always follow its tail can't box itself in. When no apple passes
+//! that check, it takes whichever move keeps the tail reachable by the
+//! longest route, to buy time for the board to open up.
+use crate::apple::AppleKind;
+use crate::occupancy::Cell;
+use crate::snake::Direction;
+use crate::Game;
+use std::collections::VecDeque;
+
+/// Queues the autopilot's next move on `game`'s snake, in place of any
+/// turns already queued.
+pub fn steer(game: &mut Game) {
+    let direction = choose(game);
+    game.snake.turns.clear();
+    game.snake.turn(direction);
+}
+
+/// The direction the snake should move in next.
+pub fn choose(game: &Game) -> Direction {
+    if let Some(route) = route_to_apple(game) {
+        if tail_reachable_after(game, &route) {
+            return route[0];
+        }
+    }
+    let mut best = None;
+    let mut best_space = None;
+    for direction in moves(game) {
+        let mut next = game.clone();
+        play(&mut next, &[direction]);
+        if next.won {
+            return direction;
+        }
+        if next.over {
+            continue;
+        }
+        if let Some(distance) = tail_distance(&next) {
+            if best.map_or(true, |(_, longest)| distance > longest) {
+                best = Some((direction, distance));
+            }
+        } else {
+            let space = reachable_cells(&next);
+            if best_space.map_or(true, |(_, most)| space > most) {
+                best_space = Some((direction, space));
+            }
+        }
+    }
+    best.or(best_space)
+        .map_or(game.snake.direction, |(direction, _)| direction)
+}
+
+/// The directions the snake can move in without reversing into its neck.
+fn moves(game: &Game) -> impl Iterator<Item = Direction> {
+    let reverse = game.snake.direction.opposite();
+    Direction::ALL
+        .iter()
+        .cloned()
+        .filter(move |direction| *direction != reverse)
+}
+
+/// Whether the head can safely be moved onto `location`, ignoring the tail
+/// moving out of the way.
+fn open(game: &Game, location: (i32, i32)) -> bool {
+    match game.occupancy().get(location) {
+        Some(Cell::Empty) => true,
+        Some(Cell::Apple) => game
+            .apples
+            .get(&location)
+            .map_or(true, |apple| apple.kind != AppleKind::Poison),
+        _ => false,
+    }
+}
+
+/// Searches outwards from the head through open cells, returning for each
+/// cell reached the direction it was first reached in from its neighbour,
+/// in the order they were reached.
+fn search(game: &Game) -> (Vec<Option<Direction>>, Vec<(i32, i32)>) {
+    let mut came_from = vec![None; game.width * game.height];
+    let mut order = vec![];
+    let mut frontier = VecDeque::new();
+    frontier.push_back(game.snake.body[0]);
+    while let Some(location) = frontier.pop_front() {
+        for direction in Direction::ALL.iter() {
+            let next = match game.snake.step(location, *direction) {
+                Some(next) => next,
+                None => continue,
+            };
+            let index = next.0 as usize * game.width + next.1 as usize;
+            if came_from[index].is_none() && open(game, next) {
+                came_from[index] = Some(*direction);
+                order.push(next);
+                frontier.push_back(next);
+            }
+        }
+    }
+    (came_from, order)
+}
+
+/// The moves along the shortest route to the nearest apple that isn't
+/// poisoned, if any can be reached.
+fn route_to_apple(game: &Game) -> Option<Vec<Direction>> {
+    let (came_from, order) = search(game);
+    let target = order
+        .into_iter()
+        .find(|location| matches!(game.occupancy().get(*location), Some(Cell::Apple)))?;
+    let head = game.snake.body[0];
+    let mut route = vec![];
+    let mut location = target;
+    while location != head {
+        let direction = came_from[location.0 as usize * game.width + location.1 as usize]?;
+        route.push(direction);
+        location = game.snake.step(location, direction.opposite())?;
+    }
+    route.reverse();
+    Some(route)
+}
+
+/// Moves `game` along `route`, one move a tick.
+fn play(game: &mut Game, route: &[Direction]) {
+    for direction in route {
+        game.snake.turns.clear();
+        game.snake.turn(*direction);
+        game.advance();
+        if game.over {
+            return;
+        }
+    }
+}
+
+/// Whether the snake survives following `route`, and can reach its tail
+/// from where it ends up.
+fn tail_reachable_after(game: &Game, route: &[Direction]) -> bool {
+    let mut after = game.clone();
+    play(&mut after, route);
+    after.won || (!after.over && tail_distance(&after).is_some())
+}
+
+/// How many moves the head is from reaching the tail's cell through open
+/// cells, or `None` if it can't get there.
+fn tail_distance(game: &Game) -> Option<usize> {
+    let tail = *game.snake.body.back()?;
+    let head = game.snake.body[0];
+    let mut distances = vec![None; game.width * game.height];
+    let mut frontier = VecDeque::new();
+    frontier.push_back((head, 0));
+    while let Some((location, distance)) = frontier.pop_front() {
+        for direction in Direction::ALL.iter() {
+            let next = match game.snake.step(location, *direction) {
+                Some(next) => next,
+                None => continue,
+            };
+            if next == tail && distance > 0 {
+                return Some(distance + 1);
+            }
+            let index = next.0 as usize * game.width + next.1 as usize;
+            if distances[index].is_none() && open(game, next) {
+                distances[index] = Some(distance + 1);
+                frontier.push_back((next, distance + 1));
+            }
+        }
+    }
+    None
+}
+
+/// How many open cells the head can reach.
+fn reachable_cells(game: &Game) -> usize {
+    search(game).1.len()
+}
+
+#[test]
+fn test_heading_for_the_apple() {
+    let mut game = Game::new(6, 6, &[(3, 3), (3, 2)], 0);
+    game.add_apple(crate::Apple::new((0, 3)));
+    assert_eq!(choose(&game), Direction::Up);
+    game.apples.clear();
+    game.rebuild_occupancy();
+    game.add_apple(crate::Apple {
+        kind: AppleKind::Poison,
+        ..crate::Apple::new((3, 4))
+    });
+    assert_ne!(choose(&game), Direction::Right);
+}
+
+#[test]
+fn test_not_boxing_itself_in() {
+    // The only apple is at the end of a dead end, where the snake would have
+    // nowhere to go after eating it.
+    let mut game = Game::new(5, 5, &[(3, 1), (3, 0)], 0);
+    for location in [(1, 1), (1, 2), (1, 3), (2, 1), (2, 3)].iter() {
+        game.add_obstacle(*location);
+    }
+    game.add_apple(crate::Apple::new((2, 2)));
+    for _ in 0..100 {
+        steer(&mut game);
+        game.advance();
+    }
+    assert!(!game.over);
+    assert_eq!(game.apples_eaten, 0);
+}
+
+#[test]
+fn test_playing_a_whole_game() {
+    let level = crate::level::Level::parse(crate::level::TERMINAL).unwrap();
+    let mut game = level.start(7);
+    while !game.over && game.ticks < 20_000 {
+        steer(&mut game);
+        game.advance();
+    }
+    assert!(game.apples_eaten > 30, "only ate {}", game.apples_eaten);
+}
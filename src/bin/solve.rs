@@ -0,0 +1,42 @@
+//! Fills an empty board with the Hamiltonian-cycle solver and reports how
+//! long it took.
+//!
+//! Usage: `solve <width> <height> [seed]`
+//!
+//! Exits with status 1 if the board has no cycle or the snake didn't fill
+//! it, or 2 if the arguments couldn't be read.
+use snake::hamiltonian;
+use std::env;
+use std::process;
+use std::time::Instant;
+
+const USAGE: &str = "Usage: solve <width> <height> [seed]";
+
+fn main() {
+    let numbers: Result<Vec<u64>, _> = env::args().skip(1).map(|arg| arg.parse()).collect();
+    let (width, height, seed) = match numbers.as_ref().map(Vec::as_slice) {
+        Ok([width, height]) => (*width, *height, 0),
+        Ok([width, height, seed]) => (*width, *height, *seed),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let start = Instant::now();
+    match hamiltonian::ticks_to_fill(width as usize, height as usize, seed) {
+        Some(ticks) => println!(
+            "Filled the {}x{} board in {} ticks ({:.2}s)",
+            width,
+            height,
+            ticks,
+            start.elapsed().as_secs_f32()
+        ),
+        None => {
+            eprintln!(
+                "Couldn't fill the {}x{} board: it needs an even side, at least 2 by 2",
+                width, height
+            );
+            process::exit(1);
+        }
+    }
+}
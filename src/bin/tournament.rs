@@ -0,0 +1,87 @@
+//! Plays agents against each other over seeded headless games.
+//!
+//! Usage: `tournament --agent NAME [--agent NAME ...] [--games N]
+//! [--json FILE] [game options]`
+//!
+//! Agents are `autopilot`, `random` or `bot:<command>`. Each plays `--games`
+//! games, 100 unless given, on the same seeds starting from `--seed`. The
+//! game options are the ones the game itself takes, like `--level` and
+//! `--special-apples`. Prints a table of results, and writes them as JSON
+//! to `--json`'s file, or to standard output if it's `-`.
+use snake::agent;
+use snake::level;
+use snake::settings::Settings;
+use snake::tournament::{self, Results};
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str =
+    "Usage: tournament --agent NAME [--agent NAME ...] [--games N] [--json FILE] [game options]";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(2);
+}
+
+fn main() {
+    let mut agents = vec![];
+    let mut games = 100;
+    let mut json = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--agent" => match args.next() {
+                Some(name) => agents.push(name),
+                None => fail("--agent expects a name"),
+            },
+            "--games" => match args.next().map(|value| value.parse()) {
+                Some(Ok(value)) if value > 0 => games = value,
+                _ => fail("--games expects a positive integer"),
+            },
+            "--json" => match args.next() {
+                Some(path) => json = Some(path),
+                None => fail("--json expects a file, or - for standard output"),
+            },
+            _ => (),
+        }
+    }
+    if agents.is_empty() {
+        fail(USAGE);
+    }
+    let settings = Settings::from_args(env::args().skip(1)).unwrap_or_else(|error| fail(&error));
+    let level = settings
+        .level(level::TERMINAL)
+        .unwrap_or_else(|error| fail(&error));
+    let start = settings.prepare(&level);
+
+    let mut players: Vec<_> = agents
+        .iter()
+        .map(|name| agent::by_name(name).unwrap_or_else(|error| fail(&error)))
+        .collect();
+
+    println!(
+        "Seeds {} to {}",
+        settings.seed,
+        settings.seed.wrapping_add(games as u64 - 1)
+    );
+    println!("{}", Results::header());
+    let mut results = vec![];
+    for (name, agent) in agents.iter().zip(players.iter_mut()) {
+        let result = tournament::run(name, agent.as_mut(), &start, games, settings.seed);
+        println!("{}", result);
+        results.push(result);
+    }
+
+    let summary = serde_json::to_string_pretty(&results).unwrap();
+    match json.as_deref() {
+        Some("-") => println!("{}", summary),
+        Some(path) => {
+            if let Err(error) = fs::write(path, summary) {
+                eprintln!("Couldn't write {}: {}", path, error);
+                process::exit(1);
+            }
+        }
+        None => (),
+    }
+}
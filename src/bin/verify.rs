@@ -0,0 +1,50 @@
+//! Checks a replay by playing it back with no rendering.
+//!
+//! Usage: `verify <replay file>`
+//!
+//! Prints how the replay ends up, and exits with status 1 if that isn't what
+//! the replay claims or it doesn't claim anything, or 2 if the replay
+//! couldn't be read. The game is started afresh from the replay's setup, so
+//! only the turns in the file are taken on trust.
+use snake::replay::{Outcome, Replay};
+use std::env;
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: verify <replay file>");
+            process::exit(2);
+        }
+    };
+    let replay = match Replay::load(Path::new(&path)) {
+        Ok(replay) => replay,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+    };
+    let outcome = Outcome::of(&replay.finish());
+    println!("Score:  {}", outcome.score);
+    println!("Length: {}", outcome.length);
+    println!("Ticks:  {}", outcome.ticks);
+    match (outcome.death, outcome.won) {
+        (Some(cause), _) => println!("Ending: the snake {}", cause),
+        (None, true) => println!("Ending: board cleared"),
+        (None, false) => println!("Ending: still going"),
+    }
+    println!("Hash:   {:016x}", outcome.hash);
+    match replay.claimed {
+        Some(claimed) if claimed == outcome => println!("Matches the claimed result"),
+        Some(claimed) => {
+            println!("MISMATCH: the replay claims {}", claimed);
+            process::exit(1);
+        }
+        None => {
+            println!("The replay doesn't claim a result");
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Real time turned into fixed-length ticks, for front-ends that draw many
+//! frames per tick.
+//!
+//! Each frame the front-end lets the time since the last one `pass`, then
+//! runs a tick for as long as `next_tick` says one is due. Time that doesn't
+//! make up a whole tick is carried over to the next frame, and how far it is
+//! through the next tick is its `progress`, for drawing between ticks.
+//!
+//! While paused no time passes, and the front-end moves the game on a tick
+//! at a time with `step` instead.
+
+/// The most ticks a single update catches up on, so a long stall doesn't
+/// leave the game racing to make up the time.
+pub const MAX_TICKS_PER_UPDATE: u32 = 5;
+
+/// The time that's passed but hasn't been simulated yet.
+pub struct Clock {
+    unsimulated: f32,
+    /// How many ticks have run since time last passed.
+    ticks: u32,
+    paused: bool,
+}
+
+impl Clock {
+    pub fn new() -> Clock {
+        Clock {
+            unsimulated: 0.0,
+            ticks: 0,
+            paused: false,
+        }
+    }
+
+    /// Lets `elapsed` seconds pass, starting a new update. Time stands still
+    /// while paused, except when `rewinding`, which works even when paused.
+    pub fn pass(&mut self, elapsed: f32, rewinding: bool) {
+        if !self.paused || rewinding {
+            self.unsimulated += elapsed;
+        }
+        self.ticks = 0;
+    }
+
+    /// Whether the clock is stopped, so the game only moves when stepped.
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stops the clock, or starts it again.
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Whether the game can be moved on a single tick, which it only can
+    /// while paused. The time left over is dropped, so the tick is drawn from
+    /// its start.
+    pub fn step(&mut self) -> bool {
+        if self.paused {
+            self.reset();
+        }
+        self.paused
+    }
+
+    /// Whether there's time for another tick lasting `seconds`, taking it
+    /// off the time left if so. Once an update has run
+    /// `MAX_TICKS_PER_UPDATE` ticks the rest of its time is dropped.
+    pub fn next_tick(&mut self, seconds: f32) -> bool {
+        if self.ticks == MAX_TICKS_PER_UPDATE {
+            self.reset();
+            return false;
+        }
+        if self.unsimulated < seconds {
+            return false;
+        }
+        self.unsimulated -= seconds;
+        self.ticks += 1;
+        true
+    }
+
+    /// Drops the time left over, for when there's nothing more to simulate
+    /// or the game has just been replaced.
+    pub fn reset(&mut self) {
+        self.unsimulated = 0.0;
+    }
+
+    /// How far through a tick lasting `seconds` the time left over is, from
+    /// 0 to 1.
+    pub fn progress(&self, seconds: f32) -> f32 {
+        (self.unsimulated / seconds).min(1.0)
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Clock {
+        Clock::new()
+    }
+}
+
+#[test]
+fn test_carrying_time_over() {
+    let mut clock = Clock::new();
+    clock.pass(0.3125, false);
+    assert!(clock.next_tick(0.125));
+    assert!(clock.next_tick(0.125));
+    assert!(!clock.next_tick(0.125));
+    assert!((clock.progress(0.125) - 0.5).abs() < f32::EPSILON);
+    clock.pass(0.0625, false);
+    assert!(clock.next_tick(0.125));
+    assert!(clock.progress(0.125) < f32::EPSILON);
+}
+
+#[test]
+fn test_catching_up_is_capped() {
+    let mut clock = Clock::new();
+    clock.pass(10.0, false);
+    let mut ticks = 0;
+    while clock.next_tick(0.1) {
+        ticks += 1;
+    }
+    assert_eq!(ticks, MAX_TICKS_PER_UPDATE);
+    assert!(clock.progress(0.1) < f32::EPSILON);
+    clock.pass(0.1, false);
+    assert!(clock.next_tick(0.1));
+}
+
+#[test]
+fn test_pausing_and_stepping() {
+    let mut clock = Clock::new();
+    assert!(!clock.step());
+    clock.pass(0.0625, false);
+    clock.toggle_pause();
+    assert!(clock.paused());
+    clock.pass(1.0, false);
+    assert!(!clock.next_tick(0.125));
+    assert!(clock.step());
+    assert!(clock.progress(0.125) < f32::EPSILON);
+    clock.pass(1.0, true);
+    assert!(clock.next_tick(0.125));
+
+    clock.toggle_pause();
+    assert!(!clock.paused());
+    clock.reset();
+    clock.pass(0.125, false);
+    assert!(clock.next_tick(0.125));
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+/// How quickly a game speeds up as the snake eats.
+///
+/// The game starts at speed level 1 and goes up a level every
+/// `apples_per_level` apples, until it reaches `max_level`. Each level
+/// shortens the tick interval by `speedup`.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Difficulty {
+    /// Seconds per tick at speed level 1.
+    pub start_seconds: f32,
+    /// What each level multiplies the tick interval by.
+    pub speedup: f32,
+    pub apples_per_level: u32,
+    /// The fastest the game gets.
+    pub max_level: u32,
+}
+
+impl Difficulty {
+    pub const EASY: Difficulty = Difficulty {
+        start_seconds: 0.25,
+        speedup: 0.95,
+        apples_per_level: 8,
+        max_level: 6,
+    };
+    pub const NORMAL: Difficulty = Difficulty {
+        start_seconds: 0.2,
+        speedup: 0.9,
+        apples_per_level: 5,
+        max_level: 10,
+    };
+    pub const HARD: Difficulty = Difficulty {
+        start_seconds: 0.15,
+        speedup: 0.85,
+        apples_per_level: 3,
+        max_level: 10,
+    };
+
+    /// The preset called `name`: `easy`, `normal` or `hard`.
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::EASY),
+            "normal" => Some(Difficulty::NORMAL),
+            "hard" => Some(Difficulty::HARD),
+            _ => None,
+        }
+    }
+
+    /// Whether this is one of the presets, rather than a curve edited into a
+    /// save or replay file.
+    pub fn is_preset(&self) -> bool {
+        [Difficulty::EASY, Difficulty::NORMAL, Difficulty::HARD].contains(self)
+    }
+
+    /// The speed level after `apples_eaten` apples.
+    pub fn level(&self, apples_eaten: u32) -> u32 {
+        (1 + apples_eaten / self.apples_per_level.max(1)).min(self.max_level)
+    }
+
+    /// How long a tick lasts at speed `level`.
+    pub fn seconds_between_ticks(&self, level: u32) -> f32 {
+        self.start_seconds * self.speedup.powi(level.saturating_sub(1) as i32)
+    }
+}
+
+impl Default for Difficulty {
+    fn default() -> Difficulty {
+        Difficulty::NORMAL
+    }
+}
+
+#[test]
+fn test_difficulty_curve() {
+    let normal = Difficulty::NORMAL;
+    assert_eq!(normal.level(0), 1);
+    assert_eq!(normal.level(4), 1);
+    assert_eq!(normal.level(5), 2);
+    assert_eq!(normal.level(1000), normal.max_level);
+    assert!(normal.seconds_between_ticks(2) < normal.seconds_between_ticks(1));
+    assert_eq!(Difficulty::from_name("hard"), Some(Difficulty::HARD));
+    assert!(Difficulty::EASY.is_preset());
+    assert!(!Difficulty {
+        max_level: 1000,
+        ..normal
+    }
+    .is_preset());
+    assert!(Difficulty::HARD.seconds_between_ticks(1) < Difficulty::EASY.seconds_between_ticks(1));
+}
@@ -0,0 +1,358 @@
+//! A gym-style environment around `Game`, for training agents.
+//!
+//! `Environment::reset` starts a fresh episode from a seed, and
+//! `Environment::step` plays one tick of it with the agent's action, handing
+//! back what the agent sees, what the tick was worth and whether the episode
+//! is over. Nothing here touches a renderer, so it works the same without the
+//! `graphics` feature.
+use crate::event::{DeathCause, Event};
+use crate::snake::Direction;
+use crate::Game;
+
+/// A move for the snake to make on the next tick.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    /// Turn a quarter to the left of where the snake is heading.
+    Left,
+    /// Carry on the way the snake is heading.
+    Straight,
+    /// Turn a quarter to the right of where the snake is heading.
+    Right,
+    /// Head in a direction on the board. Turning back on itself is ignored,
+    /// as it is from the keyboard.
+    Towards(Direction),
+}
+
+impl Action {
+    /// The actions relative to the snake's heading, for agents that pick
+    /// one of three.
+    pub const RELATIVE: [Action; 3] = [Action::Left, Action::Straight, Action::Right];
+    /// The actions on the board's own axes, for agents that pick one of
+    /// four.
+    pub const ABSOLUTE: [Action; 4] = [
+        Action::Towards(Direction::Up),
+        Action::Towards(Direction::Right),
+        Action::Towards(Direction::Down),
+        Action::Towards(Direction::Left),
+    ];
+
+    /// The direction this action asks for when the snake is heading
+    /// `heading`.
+    pub fn direction(self, heading: Direction) -> Direction {
+        match self {
+            Action::Left => heading.anticlockwise(),
+            Action::Straight => heading,
+            Action::Right => heading.clockwise(),
+            Action::Towards(direction) => direction,
+        }
+    }
+}
+
+impl From<Direction> for Action {
+    fn from(direction: Direction) -> Action {
+        Action::Towards(direction)
+    }
+}
+
+/// Works out what a tick was worth to the agent.
+///
+/// `Rewards` covers the usual shaping, and any closure taking the game and
+/// the tick's events will do for anything else.
+pub trait Reward {
+    /// The reward for the tick that produced `events`, leaving the game as
+    /// `game`.
+    fn reward(&mut self, game: &Game, events: &[Event]) -> f32;
+}
+
+impl<F: FnMut(&Game, &[Event]) -> f32> Reward for F {
+    fn reward(&mut self, game: &Game, events: &[Event]) -> f32 {
+        self(game, events)
+    }
+}
+
+/// A reward made up of a fixed amount for each thing that can happen on a
+/// tick. Any of them can be zero.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rewards {
+    /// For each apple eaten, whatever its kind.
+    pub apple: f32,
+    /// For dying, however it happened.
+    pub death: f32,
+    /// For clearing the board.
+    pub win: f32,
+    /// For every tick, usually a small penalty to stop the snake dawdling.
+    pub step: f32,
+    /// For each cell the head moves closer to the nearest apple, and taken
+    /// off for each cell it moves away. Ticks where an apple is eaten don't
+    /// count, since the nearest apple jumps somewhere else.
+    pub approach: f32,
+}
+
+impl Default for Rewards {
+    fn default() -> Rewards {
+        Rewards {
+            apple: 1.0,
+            death: -1.0,
+            win: 1.0,
+            step: 0.0,
+            approach: 0.0,
+        }
+    }
+}
+
+impl Reward for Rewards {
+    fn reward(&mut self, game: &Game, events: &[Event]) -> f32 {
+        let mut reward = self.step;
+        let ate = events
+            .iter()
+            .any(|event| matches!(event, Event::AteApple { .. }));
+        for event in events {
+            match *event {
+                Event::AteApple { .. } => reward += self.apple,
+                Event::Died(_) => reward += self.death,
+                Event::BoardFull => reward += self.win,
+                Event::Moved { from, to } if !ate && self.approach != 0.0 => {
+                    if let (Some(before), Some(after)) =
+                        (apple_distance(game, from), apple_distance(game, to))
+                    {
+                        reward += self.approach * (before as f32 - after as f32);
+                    }
+                }
+                _ => (),
+            }
+        }
+        reward
+    }
+}
+
+/// How many moves `location` is from the nearest apple, ignoring anything in
+/// the way, or `None` if there aren't any apples.
+fn apple_distance(game: &Game, (y, x): (i32, i32)) -> Option<i32> {
+    let across = |offset: i32, size: usize| {
+        let offset = offset.abs();
+        if game.snake.wraps {
+            offset.min(size as i32 - offset)
+        } else {
+            offset
+        }
+    };
+    game.apples
+        .values()
+        .map(|apple| {
+            let (apple_y, apple_x) = apple.location;
+            across(apple_y - y, game.height) + across(apple_x - x, game.width)
+        })
+        .min()
+}
+
+/// The board as one value per cell, row by row from the top left: `0` for
+/// empty cells, `1` for the snake's body, `2` for its head, `3` for apples
+/// and `4` for obstacles.
+pub fn cells(game: &Game) -> Vec<f32> {
+    let mut observation = vec![0.0; game.width * game.height];
+    let mut mark = |location: (i32, i32), value| {
+        if let Some((y, x)) = game.snake.board_location(location) {
+            observation[y as usize * game.width + x as usize] = value;
+        }
+    };
+    for obstacle in game.obstacles.iter() {
+        mark(obstacle.location, 4.0);
+    }
+    for apple in game.apples.values() {
+        mark(apple.location, 3.0);
+    }
+    for (index, location) in game.snake.body.iter().enumerate().rev() {
+        mark(*location, if index == 0 { 2.0 } else { 1.0 });
+    }
+    observation
+}
+
+/// What happened on a tick, beyond the reward.
+#[derive(Clone, Debug)]
+pub struct Info {
+    /// Everything `Game::advance` reported, in order.
+    pub events: Vec<Event>,
+    pub score: u32,
+    pub length: usize,
+    pub ticks: u32,
+    pub death: Option<DeathCause>,
+    pub won: bool,
+    /// Whether the episode was cut short by `Environment::patience`, rather
+    /// than ending with the game.
+    pub truncated: bool,
+}
+
+/// The result of `Environment::step`.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    pub reward: f32,
+    /// Whether the episode is over, and the next step should be a `reset`.
+    pub done: bool,
+    pub info: Info,
+}
+
+/// Plays episodes of snake one tick at a time for an agent.
+pub struct Environment<R: Reward = Rewards> {
+    /// The game each episode starts from, before its apples are placed, as
+    /// `Level::start` leaves it.
+    pub start: Game,
+    /// The episode being played.
+    pub game: Game,
+    pub rewards: R,
+    /// Turns the game into what the agent sees: `cells` unless it's been
+    /// changed, for instance to one of the `observation` encoders.
+    pub observe: fn(&Game) -> Vec<f32>,
+    /// How many ticks the snake can go without eating before the episode is
+    /// cut short, or `None` to let it go on for as long as the snake lives.
+    pub patience: Option<u32>,
+    hungry_ticks: u32,
+    /// Whether the episode was cut short by `patience`.
+    truncated: bool,
+}
+
+impl Environment<Rewards> {
+    /// An environment for episodes starting from `start`, with the default
+    /// `Rewards`.
+    pub fn new(start: Game) -> Environment<Rewards> {
+        Environment::with_rewards(start, Rewards::default())
+    }
+}
+
+impl<R: Reward> Environment<R> {
+    pub fn with_rewards(start: Game, rewards: R) -> Environment<R> {
+        Environment {
+            game: start.clone(),
+            start,
+            rewards,
+            observe: cells,
+            patience: None,
+            hungry_ticks: 0,
+            truncated: false,
+        }
+    }
+
+    /// Starts a new episode with apple placement seeded by `seed`, returning
+    /// the first observation.
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.game = self.start.clone();
+        self.game.reseed(seed);
+        self.game.top_up_apples();
+        self.hungry_ticks = 0;
+        self.truncated = false;
+        (self.observe)(&self.game)
+    }
+
+    /// Plays a tick with the snake following `action`. Once the episode is
+    /// over, further steps do nothing until the next `reset`, and are worth
+    /// nothing.
+    pub fn step(&mut self, action: Action) -> Step {
+        if self.game.over || self.truncated {
+            return self.finished_step(0.0, vec![]);
+        }
+        let heading = self.game.snake.direction;
+        self.game.snake.turns.clear();
+        self.game.snake.turn(action.direction(heading));
+        let events = self.game.advance();
+        if events
+            .iter()
+            .any(|event| matches!(event, Event::AteApple { .. }))
+        {
+            self.hungry_ticks = 0;
+        } else {
+            self.hungry_ticks += 1;
+        }
+        self.truncated = !self.game.over
+            && self
+                .patience
+                .map_or(false, |patience| self.hungry_ticks >= patience);
+        let reward = self.rewards.reward(&self.game, &events);
+        self.finished_step(reward, events)
+    }
+
+    /// The step for a tick that produced `events` and was worth `reward`,
+    /// leaving the game as it is now.
+    fn finished_step(&self, reward: f32, events: Vec<Event>) -> Step {
+        Step {
+            observation: (self.observe)(&self.game),
+            reward,
+            done: self.game.over || self.truncated,
+            info: Info {
+                events,
+                score: self.game.score,
+                length: self.game.snake.body.len(),
+                ticks: self.game.ticks,
+                death: self.game.death,
+                won: self.game.won,
+                truncated: self.truncated,
+            },
+        }
+    }
+}
+
+#[test]
+fn test_stepping_through_an_episode() {
+    let start = Game::new(5, 5, &[(2, 2), (2, 1)], 0);
+    let mut environment = Environment::new(start);
+    let first = environment.reset(3);
+    assert_eq!(first, environment.reset(3));
+    let codes: Vec<u32> = first.iter().map(|cell| *cell as u32).collect();
+    assert_eq!(codes.len(), 25);
+    assert_eq!(codes[2 * 5 + 2], 2);
+    assert_eq!(codes[2 * 5 + 1], 1);
+    assert_eq!(codes.iter().filter(|code| **code == 3).count(), 1);
+
+    let step = environment.step(Action::Left);
+    assert_eq!(environment.game.snake.direction, Direction::Up);
+    assert_eq!(step.observation[5 + 2] as u32, 2);
+    let step = environment.step(Action::Towards(Direction::Down));
+    assert_eq!(environment.game.snake.direction, Direction::Up);
+    assert!(!step.done);
+    let step = environment.step(Action::Straight);
+    assert!(step.done);
+    assert!(!step.info.truncated);
+    assert_eq!(step.info.death, Some(DeathCause::Wall));
+    assert_eq!(vec![step.reward], vec![-1.0]);
+    environment.rewards.step = -0.5;
+    let after = environment.step(Action::Straight);
+    assert!(after.done && after.info.events.is_empty());
+    assert_eq!(vec![after.reward], vec![0.0]);
+    assert_eq!(after.info.ticks, step.info.ticks);
+
+    environment.reset(3);
+    environment.patience = Some(2);
+    assert!(!environment.step(Action::Right).done);
+    let step = environment.step(Action::Right);
+    assert!(step.done && step.info.truncated);
+    let after = environment.step(Action::Right);
+    assert!(after.done && after.info.truncated);
+    assert_eq!(after.info.ticks, step.info.ticks);
+    assert_eq!(vec![after.reward], vec![0.0]);
+    assert!(!environment.reset(3).is_empty());
+    assert!(!environment.step(Action::Right).done);
+}
+
+#[test]
+fn test_shaping_rewards() {
+    let mut game = Game::new(5, 1, &[(0, 1), (0, 0)], 0);
+    game.add_apple(crate::Apple::new((0, 4)));
+    let rewards = Rewards {
+        step: -0.25,
+        approach: 0.5,
+        ..Rewards::default()
+    };
+    let mut environment = Environment::with_rewards(game, rewards);
+    environment.reset(0);
+    let rewards: Vec<f32> = (0..3)
+        .map(|_| environment.step(Action::Straight).reward)
+        .collect();
+    assert_eq!(rewards, vec![0.25, 0.25, 0.75]);
+
+    let ticks = |game: &Game, _: &[Event]| game.ticks as f32;
+    let mut environment = Environment::with_rewards(environment.start, ticks);
+    environment.reset(0);
+    let rewards: Vec<f32> = (0..2)
+        .map(|_| environment.step(Action::Straight).reward)
+        .collect();
+    assert_eq!(rewards, vec![1.0, 2.0]);
+}
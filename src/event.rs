@@ -0,0 +1,55 @@
+use crate::apple::AppleKind;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Something that happened during a call to `Game::advance`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Event {
+    /// The head moved from one cell to the next.
+    Moved { from: (i32, i32), to: (i32, i32) },
+    /// The snake ate the apple at `location`.
+    AteApple {
+        location: (i32, i32),
+        kind: AppleKind,
+    },
+    /// The snake kept its tail this tick and is now `length` segments long.
+    Grew { length: usize },
+    /// The snake lost segments off its tail and is now `length` long.
+    Shrank { length: usize },
+    /// The apple at `location` ran out of time and disappeared.
+    AppleExpired { location: (i32, i32) },
+    /// A new apple was placed at `location`.
+    AppleSpawned {
+        location: (i32, i32),
+        kind: AppleKind,
+    },
+    /// The snake died, ending the game.
+    Died(DeathCause),
+    /// The snake and obstacles cover the whole board, so the snake has
+    /// cleared it and won.
+    BoardFull,
+}
+
+/// What the snake ran into.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum DeathCause {
+    /// The edge of the board.
+    Wall,
+    /// Its own body.
+    OwnBody,
+    /// An obstacle tile.
+    Obstacle,
+    /// A poisoned apple.
+    Poison,
+}
+
+impl fmt::Display for DeathCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeathCause::Wall => write!(f, "hit the wall"),
+            DeathCause::OwnBody => write!(f, "ran into itself"),
+            DeathCause::Obstacle => write!(f, "hit an obstacle"),
+            DeathCause::Poison => write!(f, "ate a poisoned apple"),
+        }
+    }
+}
@@ -0,0 +1,588 @@
+use crate::apple::{Apple, AppleKind, Apples};
+use crate::difficulty::Difficulty;
+use crate::event::{DeathCause, Event};
+use crate::obstacle::Obstacle;
+use crate::occupancy::{Cell, Occupancy};
+use crate::snake::Snake;
+use rand::prelude::*;
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// How much shorter ticks are while a speed apple is in effect.
+const SPEED_BOOST_FACTOR: f32 = 0.6;
+
+/// The state of a single game of snake on a `width` by `height` board.
+///
+/// Apple placement is driven by an RNG seeded from `seed`, so two games
+/// created with the same arguments and fed the same moves play out
+/// identically.
+///
+/// The game keeps an `Occupancy` grid of the board in step with the snake,
+/// apples and obstacles, so collisions and apple placement don't depend on
+/// the snake's length. Changing those fields directly needs a call to
+/// `rebuild_occupancy` afterwards; `add_apple` and `add_obstacle` keep it up
+/// to date themselves.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Game {
+    /// Set once the snake has died or cleared the board, after which
+    /// `advance` does nothing.
+    pub over: bool,
+    /// Set when the snake has filled every free cell, winning the game.
+    pub won: bool,
+    /// What killed the snake, once it's dead.
+    #[serde(default)]
+    pub death: Option<DeathCause>,
+    pub snake: Snake,
+    #[serde(with = "crate::apple::by_location")]
+    pub apples: Apples,
+    /// Wall tiles that stay put for the whole game.
+    pub obstacles: HashSet<Obstacle>,
+    pub width: usize,
+    pub height: usize,
+    /// How many times `advance` has moved the snake.
+    #[serde(default)]
+    pub ticks: u32,
+    /// Points from the apples eaten so far.
+    pub score: u32,
+    /// How many apples the snake has eaten, whatever their kind.
+    pub apples_eaten: u32,
+    /// How the tick interval shrinks as `apples_eaten` goes up.
+    pub difficulty: Difficulty,
+    /// Whether new apples can be special kinds, rather than always `Normal`.
+    pub special_apples: bool,
+    /// How many more ticks a speed apple keeps the game running faster.
+    pub speed_boost: u32,
+    /// How many ticks new apples last before disappearing and turning up
+    /// somewhere else, or `None` to keep them until they're eaten.
+    pub apple_lifetime: Option<u32>,
+    /// How many apples `advance` keeps on the board.
+    pub apple_target: usize,
+    /// The seed the apple placement RNG was created from.
+    pub seed: u64,
+    rng: Pcg64,
+    #[serde(skip)]
+    occupancy: Occupancy,
+}
+
+impl Game {
+    /// Creates a game with no apples, with the snake heading away from its
+    /// second segment. `snake_body` is given head first as `(y, x)` pairs.
+    pub fn new(width: usize, height: usize, snake_body: &[(i32, i32)], seed: u64) -> Game {
+        let mut game = Game {
+            over: false,
+            won: false,
+            death: None,
+            snake: Snake {
+                body: VecDeque::from(Vec::from(snake_body)),
+                growth: 0,
+                direction: Snake::head_direction(
+                    snake_body.iter(),
+                    (height as i32, width as i32),
+                    false,
+                ),
+                turns: VecDeque::new(),
+                confines: (height as i32, width as i32),
+                wraps: false,
+                confines_size: (550.0, 550.0),
+            },
+            ticks: 0,
+            score: 0,
+            apples_eaten: 0,
+            difficulty: Difficulty::default(),
+            special_apples: false,
+            speed_boost: 0,
+            apple_lifetime: None,
+            apple_target: 1,
+            apples: HashMap::new(),
+            obstacles: HashSet::new(),
+            width,
+            height,
+            seed,
+            rng: Pcg64::seed_from_u64(seed),
+            occupancy: Occupancy::default(),
+        };
+        game.rebuild_occupancy();
+        game
+    }
+
+    /// Restarts the apple placement RNG from `seed`, as if the game had been
+    /// created with it.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = Pcg64::seed_from_u64(seed);
+    }
+
+    /// The occupancy grid, for looking up what's on a cell in O(1).
+    pub fn occupancy(&self) -> &Occupancy {
+        &self.occupancy
+    }
+
+    /// Works the occupancy grid out again from `snake`, `apples` and
+    /// `obstacles`, after any of them have been changed directly.
+    pub fn rebuild_occupancy(&mut self) {
+        let mut cells = vec![Cell::Empty; self.width * self.height];
+        let mut cover = |(y, x): (i32, i32), cell| {
+            if y >= 0 && x >= 0 && (y as usize) < self.height && (x as usize) < self.width {
+                cells[y as usize * self.width + x as usize] = cell;
+            }
+        };
+        for location in self.snake.body.iter() {
+            cover(*location, Cell::Snake);
+        }
+        for apple in self.apples.values() {
+            cover(apple.location, Cell::Apple);
+        }
+        for obstacle in self.obstacles.iter() {
+            cover(obstacle.location, Cell::Obstacle);
+        }
+        self.occupancy = Occupancy::from_cells(self.width, self.height, cells);
+    }
+
+    /// Puts `apple` on the board, unless its cell is already taken. Returns
+    /// whether it was placed.
+    pub fn add_apple(&mut self, apple: Apple) -> bool {
+        if self.occupancy.get(apple.location) != Some(Cell::Empty) {
+            return false;
+        }
+        self.occupancy.set(apple.location, Cell::Apple);
+        self.apples.insert(apple.location, apple);
+        true
+    }
+
+    /// Puts an obstacle on `location`, replacing any apple there.
+    pub fn add_obstacle(&mut self, location: (i32, i32)) {
+        self.apples.remove(&location);
+        self.occupancy.set(location, Cell::Obstacle);
+        self.obstacles.insert(Obstacle { location });
+    }
+
+    /// A fingerprint of where the snake and apples are, the score and the
+    /// tick count, which is the same for two games exactly when they've
+    /// played out the same way. It's stable across runs and platforms.
+    pub fn state_hash(&self) -> u64 {
+        // 64-bit FNV-1a, which unlike `DefaultHasher` is fixed for good.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut add = |value: i64| {
+            for byte in value.to_le_bytes().iter() {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        add(self.width as i64);
+        add(self.height as i64);
+        add(i64::from(self.ticks));
+        add(i64::from(self.score));
+        add(i64::from(self.snake.growth));
+        add(self.snake.direction as i64);
+        for (y, x) in self.snake.body.iter() {
+            add(i64::from(*y));
+            add(i64::from(*x));
+        }
+        let mut apples: Vec<_> = self.apples.values().collect();
+        apples.sort_unstable_by_key(|apple| apple.location);
+        for apple in apples {
+            add(i64::from(apple.location.0));
+            add(i64::from(apple.location.1));
+            add(apple.kind as i64);
+            add(apple.lifetime.map_or(-1, i64::from));
+        }
+        hash
+    }
+
+    /// The current speed level on the `difficulty` curve, starting at 1.
+    pub fn speed_level(&self) -> u32 {
+        self.difficulty.level(self.apples_eaten)
+    }
+
+    /// How long the real-time front-end should wait between calls to
+    /// `advance`, from the speed level and any speed apple in effect.
+    pub fn seconds_between_ticks(&self) -> f32 {
+        let seconds = self.difficulty.seconds_between_ticks(self.speed_level());
+        if self.speed_boost > 0 {
+            seconds * SPEED_BOOST_FACTOR
+        } else {
+            seconds
+        }
+    }
+
+    /// Moves the snake one cell, applying the effect of any apple under its
+    /// new head. Apples that run out of time are moved elsewhere, and the
+    /// board is topped back up to `apple_target` apples. Returns everything
+    /// that happened, in order.
+    pub fn advance(&mut self) -> Vec<Event> {
+        let mut events = vec![];
+        if self.over {
+            return events;
+        }
+        self.ticks += 1;
+        self.speed_boost = self.speed_boost.saturating_sub(1);
+        self.age_apples(&mut events);
+        let from = self.snake.body[0];
+        let growing = self.snake.growth > 0;
+        let tail = self.snake.body.back().cloned();
+        self.snake.advance();
+        let head = self.snake.body[0];
+        events.push(Event::Moved { from, to: head });
+        if growing {
+            events.push(Event::Grew {
+                length: self.snake.body.len(),
+            });
+        } else if let Some(tail) = tail {
+            self.occupancy.set(tail, Cell::Empty);
+        }
+        let cause = match self.occupancy.get(head) {
+            None => Some(DeathCause::Wall),
+            Some(Cell::Snake) => Some(DeathCause::OwnBody),
+            Some(Cell::Obstacle) => Some(DeathCause::Obstacle),
+            Some(Cell::Empty) | Some(Cell::Apple) => None,
+        };
+        if let Some(cause) = cause {
+            self.over = true;
+            self.death = Some(cause);
+            events.push(Event::Died(cause));
+            return events;
+        }
+        self.occupancy.set(head, Cell::Snake);
+        if let Some(apple) = self.apples.remove(&head) {
+            events.push(Event::AteApple {
+                location: head,
+                kind: apple.kind,
+            });
+            if !self.eat(apple, &mut events) {
+                return events;
+            }
+        }
+        events.extend(self.top_up_apples());
+        events
+    }
+
+    /// Places new apples until there are `apple_target` of them and, if
+    /// there are any, at least one isn't poisoned, or until there's nowhere
+    /// left to put them.
+    pub fn top_up_apples(&mut self) -> Vec<Event> {
+        let mut events = vec![];
+        while self.apples.len() < self.apple_target
+            || !self.apples.is_empty()
+                && self
+                    .apples
+                    .values()
+                    .all(|apple| apple.kind == AppleKind::Poison)
+        {
+            if !self.spawn_apple(&mut events) {
+                break;
+            }
+        }
+        events
+    }
+
+    /// Counts down the lifetime of every apple that has one, moving the ones
+    /// that run out to a different free cell.
+    fn age_apples(&mut self, events: &mut Vec<Event>) {
+        if self.apples.values().all(|apple| apple.lifetime.is_none()) {
+            return;
+        }
+        let mut expired = vec![];
+        self.apples = self
+            .apples
+            .drain()
+            .filter_map(|(location, mut apple)| match apple.lifetime {
+                Some(ticks) if ticks <= 1 => {
+                    expired.push(apple.location);
+                    None
+                }
+                Some(ticks) => {
+                    apple.lifetime = Some(ticks - 1);
+                    Some((location, apple))
+                }
+                None => Some((location, apple)),
+            })
+            .collect();
+        // The expired cells stay taken until the replacements are placed, so
+        // an apple never turns up where it just vanished.
+        expired.sort_unstable();
+        let replacements: Vec<_> = expired
+            .iter()
+            .filter_map(|_| self.add_new_apple())
+            .collect();
+        for location in expired {
+            self.occupancy.set(location, Cell::Empty);
+            events.push(Event::AppleExpired { location });
+        }
+        for apple in replacements {
+            events.push(Event::AppleSpawned {
+                location: apple.location,
+                kind: apple.kind,
+            });
+        }
+    }
+
+    /// Places a new apple, returning whether there was anywhere to put one.
+    /// If there wasn't because the snake and obstacles cover the whole board,
+    /// the game is won.
+    fn spawn_apple(&mut self, events: &mut Vec<Event>) -> bool {
+        match self.add_new_apple() {
+            Some(apple) => {
+                events.push(Event::AppleSpawned {
+                    location: apple.location,
+                    kind: apple.kind,
+                });
+                true
+            }
+            None => {
+                if self.apples.is_empty() && self.occupancy.empty_cells() == 0 {
+                    self.won = true;
+                    self.over = true;
+                    events.push(Event::BoardFull);
+                }
+                false
+            }
+        }
+    }
+
+    /// Applies the effects of eating `apple`, returning whether the snake
+    /// survived it.
+    fn eat(&mut self, apple: Apple, events: &mut Vec<Event>) -> bool {
+        if apple.kind == AppleKind::Poison {
+            self.over = true;
+            self.death = Some(DeathCause::Poison);
+            events.push(Event::Died(DeathCause::Poison));
+            return false;
+        }
+        self.score += apple.kind.points();
+        self.apples_eaten += 1;
+        self.snake.growth += apple.kind.growth();
+        let shed = self.snake.shrink(apple.kind.shrinkage());
+        if !shed.is_empty() {
+            for location in shed {
+                self.occupancy.set(location, Cell::Empty);
+            }
+            events.push(Event::Shrank {
+                length: self.snake.body.len(),
+            });
+        }
+        self.speed_boost = self.speed_boost.max(apple.kind.speed_boost());
+        true
+    }
+
+    /// Places an apple on a random cell not covered by the snake, an obstacle
+    /// or another apple, returning it, or `None` if the board is full. A
+    /// wrapping snake's body never leaves the board, so every free cell is a
+    /// candidate. The apple is `Normal` unless `special_apples` is set, and
+    /// lasts for `apple_lifetime` ticks.
+    pub fn add_new_apple(&mut self) -> Option<Apple> {
+        let location = self.occupancy.random_empty(&mut self.rng)?;
+        let kind = if self.special_apples {
+            AppleKind::random(&mut self.rng)
+        } else {
+            AppleKind::Normal
+        };
+        let apple = Apple {
+            location,
+            kind,
+            lifetime: self.apple_lifetime,
+        };
+        self.add_apple(apple);
+        Some(apple)
+    }
+}
+
+#[test]
+fn test_same_seed_places_same_apples() {
+    let body = [(10, 10), (10, 9), (10, 8)];
+    let mut first = Game::new(20, 20, &body, 1234);
+    let mut second = Game::new(20, 20, &body, 1234);
+    for _ in 0..5 {
+        first.add_new_apple();
+        second.add_new_apple();
+    }
+    assert_eq!(first.apples, second.apples);
+}
+
+#[test]
+fn test_apples_avoid_obstacles() {
+    let mut game = Game::new(2, 2, &[(1, 0), (1, 1)], 0);
+    game.add_obstacle((0, 0));
+    game.add_new_apple();
+    assert!(game.apples.contains_key(&(0, 1)));
+}
+
+#[test]
+fn test_advance_events() {
+    let mut game = Game::new(5, 5, &[(2, 1), (2, 0)], 0);
+    game.add_apple(Apple::new((2, 2)));
+    let events = game.advance();
+    assert_eq!(
+        events[0],
+        Event::Moved {
+            from: (2, 1),
+            to: (2, 2)
+        }
+    );
+    assert_eq!(
+        events[1],
+        Event::AteApple {
+            location: (2, 2),
+            kind: AppleKind::Normal
+        }
+    );
+    assert!(matches!(events[2], Event::AppleSpawned { .. }));
+    assert_eq!(events.len(), 3);
+    assert_eq!(game.score, 1);
+
+    let events = game.advance();
+    assert_eq!(events[1], Event::Grew { length: 3 });
+
+    game.add_obstacle((1, 3));
+    game.snake.turn(crate::Direction::Up);
+    assert_eq!(
+        game.advance().last(),
+        Some(&Event::Died(DeathCause::Obstacle))
+    );
+    assert!(game.over);
+    assert!(game.advance().is_empty());
+
+    let mut game = Game::new(2, 2, &[(0, 1), (0, 0)], 0);
+    assert_eq!(game.advance().last(), Some(&Event::Died(DeathCause::Wall)));
+}
+
+#[test]
+fn test_clearing_the_board() {
+    let mut game = Game::new(2, 2, &[(0, 0), (0, 1), (1, 1)], 0);
+    game.add_apple(Apple::new((1, 0)));
+    game.snake.turn(crate::Direction::Down);
+    assert_eq!(
+        game.advance().last(),
+        Some(&Event::AppleSpawned {
+            location: (1, 1),
+            kind: AppleKind::Normal
+        })
+    );
+    game.snake.turn(crate::Direction::Right);
+    assert_eq!(game.advance().last(), Some(&Event::BoardFull));
+    assert!(game.won && game.over);
+    assert_eq!(game.score, 2);
+}
+
+#[test]
+fn test_apple_kinds() {
+    let mut game = Game::new(9, 1, &[(0, 4), (0, 3), (0, 2), (0, 1), (0, 0)], 0);
+    let kinds = [
+        AppleKind::Golden,
+        AppleKind::Shrinking,
+        AppleKind::Speed,
+        AppleKind::Poison,
+    ];
+    for (x, kind) in kinds.iter().enumerate() {
+        game.add_apple(Apple {
+            kind: *kind,
+            ..Apple::new((0, 5 + x as i32))
+        });
+    }
+    game.advance();
+    assert_eq!((game.score, game.snake.growth), (5, 1));
+    let events = game.advance();
+    assert!(events.contains(&Event::Shrank { length: 4 }));
+    assert_eq!(game.occupancy().empty_cells(), 3);
+    game.advance();
+    assert_eq!(game.score, 7);
+    assert!(game.seconds_between_ticks() < Difficulty::NORMAL.start_seconds);
+    assert_eq!(
+        game.advance().last(),
+        Some(&Event::Died(DeathCause::Poison))
+    );
+}
+
+#[test]
+fn test_apples_expire() {
+    let mut game = Game::new(5, 5, &[(0, 1), (0, 0)], 0);
+    game.apple_lifetime = Some(2);
+    game.add_new_apple();
+    let first = game.apples.values().next().unwrap().location;
+    game.advance();
+    assert_eq!(game.apples.values().next().unwrap().lifetime, Some(1));
+    let events = game.advance();
+    assert_eq!(events[0], Event::AppleExpired { location: first });
+    assert!(matches!(events[1], Event::AppleSpawned { .. }));
+    assert_eq!(game.apples.len(), 1);
+    let second = game.apples.values().next().unwrap();
+    assert_ne!(second.location, first);
+    assert_eq!(second.lifetime, Some(2));
+    assert_eq!(game.occupancy().empty_cells(), 25 - 2 - 1);
+}
+
+#[test]
+fn test_apple_target() {
+    let mut game = Game::new(5, 5, &[(0, 1), (0, 0)], 0);
+    game.apple_target = 4;
+    assert_eq!(game.top_up_apples().len(), 4);
+    let location = *game.apples.keys().next().unwrap();
+    game.apples.remove(&location);
+    game.rebuild_occupancy();
+    assert_eq!(game.top_up_apples().len(), 1);
+    assert_eq!(game.apples.len(), 4);
+
+    let mut game = Game::new(5, 5, &[(0, 1), (0, 0)], 0);
+    game.apple_target = 0;
+    assert!(game.top_up_apples().is_empty());
+    assert!(game.apples.is_empty());
+
+    // Running out of room for apples only wins once the snake fills the
+    // board.
+    let mut game = Game::new(2, 2, &[(0, 1), (0, 0)], 0);
+    game.apple_target = 3;
+    assert_eq!(game.top_up_apples().len(), 2);
+    assert_eq!(game.apples.len(), 2);
+    assert!(!game.won && !game.over);
+
+    let mut game = Game::new(2, 2, &[(0, 1), (0, 0)], 0);
+    for location in [(1, 0), (1, 1)].iter() {
+        game.add_apple(Apple {
+            kind: AppleKind::Poison,
+            ..Apple::new(*location)
+        });
+    }
+    assert!(game.top_up_apples().is_empty());
+    assert!(!game.won && !game.over);
+
+    let mut game = Game::new(2, 1, &[(0, 1), (0, 0)], 0);
+    assert_eq!(game.top_up_apples(), vec![Event::BoardFull]);
+    assert!(game.won && game.over);
+}
+
+#[test]
+fn test_speeding_up() {
+    let mut game = Game::new(9, 1, &[(0, 1), (0, 0)], 0);
+    game.difficulty = Difficulty {
+        apples_per_level: 2,
+        max_level: 2,
+        ..Difficulty::NORMAL
+    };
+    for x in 2..7 {
+        game.add_apple(Apple::new((0, x)));
+    }
+    let start = game.seconds_between_ticks();
+    game.advance();
+    assert_eq!(game.speed_level(), 1);
+    game.advance();
+    assert_eq!(game.speed_level(), 2);
+    assert!(game.seconds_between_ticks() < start);
+    game.advance();
+    game.advance();
+    assert_eq!(game.speed_level(), 2);
+}
+
+#[test]
+fn test_state_hash() {
+    let mut first = Game::new(5, 5, &[(2, 1), (2, 0)], 3);
+    let mut second = first.clone();
+    first.add_apple(Apple::new((0, 0)));
+    first.add_apple(Apple::new((4, 4)));
+    second.add_apple(Apple::new((4, 4)));
+    second.add_apple(Apple::new((0, 0)));
+    assert_eq!(first.state_hash(), second.state_hash());
+    first.advance();
+    assert_ne!(first.state_hash(), second.state_hash());
+    second.advance();
+    assert_eq!(first.state_hash(), second.state_hash());
+}
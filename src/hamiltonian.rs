@@ -0,0 +1,219 @@
+//! A solver that can't lose, for boards with an even side.
+//!
+//! A Hamiltonian cycle is a route that visits every cell of the board once
+//! and comes back to where it started. A snake that only ever moves along
+//! one never runs into itself, since its tail is always somewhere behind its
+//! head on the route, so it keeps eating until the board is full. Following
+//! the whole cycle for every apple is slow, so while the snake is short it
+//! cuts across to cells further along the cycle, as long as that leaves
+//! plenty of room before the tail.
+//!
+//! The guarantee only holds for ordinary apples on a board without
+//! obstacles: a poisoned apple on the cycle gets eaten like any other.
+use crate::occupancy::Cell;
+use crate::snake::{Direction, Snake};
+use crate::Game;
+
+/// A cycle through every cell of a `width` by `height` board.
+pub struct Cycle {
+    pub width: usize,
+    pub height: usize,
+    /// The cells in the order the cycle visits them.
+    cells: Vec<(i32, i32)>,
+    /// How far along the cycle each cell is, row by row.
+    positions: Vec<usize>,
+}
+
+impl Cycle {
+    /// Builds a cycle over a `width` by `height` board, or returns `None` if
+    /// there isn't one, because both sides are odd or either is shorter than
+    /// two cells.
+    ///
+    /// The cycle runs along the top row, snakes back and forth over every
+    /// other column a row at a time, then comes back up the first column.
+    pub fn new(width: usize, height: usize) -> Option<Cycle> {
+        if width < 2 || height < 2 {
+            return None;
+        }
+        let cells = if height % 2 == 0 {
+            Cycle::rows(width, height)
+        } else if width % 2 == 0 {
+            Cycle::rows(height, width)
+                .into_iter()
+                .map(|(y, x)| (x, y))
+                .collect()
+        } else {
+            return None;
+        };
+        let mut positions = vec![0; width * height];
+        for (position, (y, x)) in cells.iter().enumerate() {
+            positions[*y as usize * width + *x as usize] = position;
+        }
+        Some(Cycle {
+            width,
+            height,
+            cells,
+            positions,
+        })
+    }
+
+    /// The cycle for a board with an even `height`.
+    fn rows(width: usize, height: usize) -> Vec<(i32, i32)> {
+        let (width, height) = (width as i32, height as i32);
+        let mut cells: Vec<(i32, i32)> = (0..width).map(|x| (0, x)).collect();
+        for y in 1..height {
+            if y % 2 == 1 {
+                cells.extend((1..width).rev().map(|x| (y, x)));
+            } else {
+                cells.extend((1..width).map(|x| (y, x)));
+            }
+        }
+        cells.extend((1..height).rev().map(|y| (y, 0)));
+        cells
+    }
+
+    /// How many cells the cycle goes through.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// How far along the cycle `location` is.
+    pub fn position(&self, (y, x): (i32, i32)) -> usize {
+        self.positions[y as usize * self.width + x as usize]
+    }
+
+    /// How many moves along the cycle it takes to get from `from` to `to`.
+    pub fn distance(&self, from: (i32, i32), to: (i32, i32)) -> usize {
+        (self.position(to) + self.len() - self.position(from)) % self.len()
+    }
+
+    /// The cell after `location` on the cycle.
+    pub fn next(&self, location: (i32, i32)) -> (i32, i32) {
+        self.cells[(self.position(location) + 1) % self.len()]
+    }
+
+    /// Starts a game on an empty board with a two segment snake lying along
+    /// the start of the cycle, with apple placement seeded by `seed`.
+    pub fn start(&self, seed: u64) -> Game {
+        let mut game = Game::new(
+            self.width,
+            self.height,
+            &[self.cells[1], self.cells[0]],
+            seed,
+        );
+        game.top_up_apples();
+        game
+    }
+
+    /// The direction the snake should move in next. The snake has to be
+    /// lying along the cycle, as it is in a game from `start`: every segment
+    /// further along it than the one behind.
+    pub fn choose(&self, game: &Game) -> Direction {
+        let head = game.snake.body[0];
+        let mut best = self.next(head);
+        if let Some(shortcut) = self.shortcut(game) {
+            best = shortcut;
+        }
+        Snake::direction(best, head, game.snake.confines, game.snake.wraps)
+    }
+
+    /// Queues the solver's next move on `game`'s snake, in place of any
+    /// turns already queued.
+    pub fn steer(&self, game: &mut Game) {
+        let direction = self.choose(game);
+        game.snake.turns.clear();
+        game.snake.turn(direction);
+    }
+
+    /// The neighbour of the head furthest along the cycle without going past
+    /// the nearest apple, if it's worth cutting across to. It has to leave
+    /// more than twice the snake's length, pending growth included, free on
+    /// the cycle before the tail, so the tail is always out of the way by
+    /// the time the head comes round to it.
+    fn shortcut(&self, game: &Game) -> Option<(i32, i32)> {
+        let head = game.snake.body[0];
+        let tail = *game.snake.body.back()?;
+        let to_apple = game
+            .apples
+            .values()
+            .map(|apple| self.distance(head, apple.location))
+            .min()?;
+        let to_tail = self.distance(head, tail);
+        let room = 2 * (game.snake.body.len() + game.snake.growth as usize);
+        let mut best = None;
+        let mut best_distance = 1;
+        for direction in Direction::ALL.iter() {
+            let neighbour = match game.snake.step(head, *direction) {
+                Some(neighbour) => neighbour,
+                None => continue,
+            };
+            let free = matches!(
+                game.occupancy().get(neighbour),
+                Some(Cell::Empty) | Some(Cell::Apple)
+            );
+            if !free {
+                continue;
+            }
+            let distance = self.distance(head, neighbour);
+            if distance > best_distance && distance <= to_apple && distance + room < to_tail {
+                best = Some(neighbour);
+                best_distance = distance;
+            }
+        }
+        best
+    }
+}
+
+/// Plays a game on an empty `width` by `height` board with the solver,
+/// returning how many ticks it took to fill the board, or `None` if there's
+/// no cycle for the board or the snake somehow didn't fill it.
+pub fn ticks_to_fill(width: usize, height: usize, seed: u64) -> Option<u32> {
+    let cycle = Cycle::new(width, height)?;
+    let mut game = cycle.start(seed);
+    while !game.over {
+        cycle.steer(&mut game);
+        game.advance();
+    }
+    if game.won {
+        Some(game.ticks)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_cycles_visit_every_cell() {
+    for (width, height) in [(2, 2), (4, 4), (5, 4), (4, 5), (7, 2)].iter() {
+        let cycle = Cycle::new(*width, *height).unwrap();
+        assert_eq!(cycle.len(), width * height);
+        let mut location = (0, 0);
+        let mut visited = std::collections::HashSet::new();
+        for _ in 0..cycle.len() {
+            let next = cycle.next(location);
+            let (dy, dx) = (next.0 - location.0, next.1 - location.1);
+            assert_eq!(dy.abs() + dx.abs(), 1);
+            assert!(next.0 >= 0 && next.1 >= 0);
+            assert!((next.0 as usize) < *height && (next.1 as usize) < *width);
+            visited.insert(next);
+            location = next;
+        }
+        assert_eq!(location, (0, 0));
+        assert_eq!(visited.len(), cycle.len());
+    }
+    assert!(Cycle::new(5, 5).is_none());
+    assert!(Cycle::new(1, 4).is_none());
+}
+
+#[test]
+fn test_filling_the_board() {
+    for seed in 0..5 {
+        let ticks = ticks_to_fill(6, 6, seed).unwrap();
+        assert!(ticks > 34);
+    }
+    let ticks = ticks_to_fill(10, 7, 0).unwrap();
+    assert!(ticks < 70 * 70);
+}
@@ -0,0 +1,73 @@
+use crate::Game;
+use std::collections::VecDeque;
+
+/// How many ticks the front-ends can rewind by.
+pub const REWIND_TICKS: usize = 100;
+
+/// Snapshots of a game from the last `capacity` ticks, oldest first, for
+/// stepping it back in time.
+pub struct History {
+    snapshots: VecDeque<Game>,
+    pub capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> History {
+        History {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Remembers `game` as it is now, forgetting the oldest snapshot once
+    /// there are `capacity` of them.
+    pub fn record(&mut self, game: &Game) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(game.clone());
+    }
+
+    /// Takes back the most recent snapshot, or `None` if there's nothing
+    /// left to rewind to.
+    pub fn rewind(&mut self) -> Option<Game> {
+        self.snapshots.pop_back()
+    }
+
+    /// How many ticks can be rewound.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+#[test]
+fn test_rewinding() {
+    let mut game = Game::new(5, 5, &[(0, 1), (0, 0)], 0);
+    let mut history = History::new(2);
+    for _ in 0..4 {
+        history.record(&game);
+        game.advance();
+    }
+    assert!(game.over);
+    assert_eq!(history.len(), 2);
+    let rewound = history.rewind().unwrap();
+    assert!(!rewound.over);
+    assert_eq!(rewound.snake.body[0], (0, 4));
+    assert_eq!(
+        rewound.occupancy().get((0, 4)),
+        Some(crate::occupancy::Cell::Snake)
+    );
+    assert_eq!(history.rewind().unwrap().snake.body[0], (0, 3));
+    assert!(history.rewind().is_none());
+}
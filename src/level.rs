@@ -0,0 +1,329 @@
+//! Starting boards described as plain text.
+//!
+//! A level is drawn the same way the print renderer draws a game: a border of
+//! `-` above and below the board, `|` either side of each row, and one glyph
+//! per cell in between. The board's size comes from the border.
+//!
+//! | Glyph         | Cell                                          |
+//! |---------------|-----------------------------------------------|
+//! | ` `           | Empty                                         |
+//! | `#`           | An obstacle                                   |
+//! | `O`           | An apple                                      |
+//! | `$ @ o ! X`   | A golden, feast, shrinking, speed or poisoned |
+//! |               | apple                                         |
+//! | `^ > v <`     | The snake's head, pointing ahead              |
+//! | `║ ═ ╔ ╗ ╚ ╝` | The rest of the snake's body                  |
+//!
+//! There must be exactly one snake, with at least one body segment behind its
+//! head and every body glyph joined up to it.
+//!
+//! Besides the two built-in boards, `levels/arena.txt` has a walled arena in
+//! the middle of the board, played with `--level levels/arena.txt`.
+use crate::apple::{Apple, AppleKind};
+use crate::snake::{Direction, Snake};
+use crate::Game;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The board the terminal front-end starts on.
+pub const TERMINAL: &str = include_str!("../levels/terminal.txt");
+/// The board the ggez front-end starts on.
+pub const CLASSIC: &str = include_str!("../levels/classic.txt");
+
+/// A parsed starting board, which can start any number of games.
+#[derive(Debug)]
+pub struct Level {
+    /// The text the level was parsed from, which replays keep to build it
+    /// again.
+    pub text: String,
+    pub width: usize,
+    pub height: usize,
+    /// The snake's cells, head first.
+    pub snake: Vec<(i32, i32)>,
+    pub apples: Vec<Apple>,
+    pub obstacles: Vec<(i32, i32)>,
+}
+
+/// Why a level couldn't be parsed, at a 1-based `line` and `column`.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+fn error<T>((y, x): (usize, usize), message: &str) -> Result<T, ParseError> {
+    Err(ParseError {
+        line: y + 1,
+        column: x + 1,
+        message: String::from(message),
+    })
+}
+
+fn head_direction(glyph: char) -> Option<Direction> {
+    Direction::ALL
+        .iter()
+        .cloned()
+        .find(|direction| Snake::get_head_glyph(*direction) == glyph)
+}
+
+fn is_body_glyph(glyph: char) -> bool {
+    "║═╔╗╚╝".contains(glyph)
+}
+
+impl Level {
+    /// Reads and parses the level file at `path`.
+    pub fn load(path: &Path) -> Result<Level, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+        Level::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    pub fn parse(text: &str) -> Result<Level, ParseError> {
+        let lines: Vec<Vec<char>> = text
+            .trim_end_matches('\n')
+            .lines()
+            .map(|line| line.chars().collect())
+            .collect();
+        let top = lines.get(0).map(Vec::as_slice).unwrap_or(&[]);
+        if top.len() < 3 || top.iter().any(|c| *c != '-') {
+            return error((0, 0), "expected a border of at least three '-'");
+        }
+        let width = top.len() - 2;
+        let bottom = match lines
+            .iter()
+            .skip(1)
+            .position(|line| line.get(0) == Some(&'-'))
+        {
+            Some(index) => index + 1,
+            None => return error((lines.len(), 0), "expected a closing border of '-'"),
+        };
+        if lines[bottom].len() != top.len() || lines[bottom].iter().any(|c| *c != '-') {
+            return error((bottom, 0), "expected the same border as the first line");
+        }
+        if bottom + 1 < lines.len() {
+            return error((bottom + 1, 0), "unexpected text after the closing border");
+        }
+        let height = bottom - 1;
+        if height == 0 {
+            return error((1, 0), "expected at least one row");
+        }
+
+        let mut level = Level {
+            text: String::from(text),
+            width,
+            height,
+            snake: vec![],
+            apples: vec![],
+            obstacles: vec![],
+        };
+        let mut head = None;
+        for (y, line) in lines.iter().enumerate().take(bottom).skip(1) {
+            if line.first() != Some(&'|') {
+                return error((y, 0), "expected '|'");
+            }
+            if line.len() != width + 2 || line[width + 1] != '|' {
+                return error((y, width + 1), "expected '|'");
+            }
+            for (x, glyph) in line.iter().enumerate().take(width + 1).skip(1) {
+                let location = ((y - 1) as i32, (x - 1) as i32);
+                match *glyph {
+                    ' ' => (),
+                    '#' => level.obstacles.push(location),
+                    glyph if AppleKind::from_glyph(glyph).is_some() => {
+                        let kind = AppleKind::from_glyph(glyph).unwrap();
+                        level.apples.push(Apple {
+                            kind,
+                            ..Apple::new(location)
+                        });
+                    }
+                    glyph if head_direction(glyph).is_some() => {
+                        if head.is_some() {
+                            return error((y, x), "found a second snake head");
+                        }
+                        head = Some((location, head_direction(glyph).unwrap()));
+                    }
+                    glyph if is_body_glyph(glyph) => (),
+                    glyph => return error((y, x), &format!("unexpected '{}'", glyph)),
+                }
+            }
+        }
+        let (head, direction) = match head {
+            Some(head) => head,
+            None => return error((0, 0), "there's no snake head on the board"),
+        };
+        level.snake = Level::trace_snake(&lines, (width, height), head, direction)?;
+        Ok(level)
+    }
+
+    /// Follows the body glyphs back from the head, checking each one joins
+    /// up with the segment in front of it.
+    fn trace_snake(
+        lines: &[Vec<char>],
+        (width, height): (usize, usize),
+        head: (i32, i32),
+        direction: Direction,
+    ) -> Result<Vec<(i32, i32)>, ParseError> {
+        let glyph_at = |(y, x): (i32, i32)| -> Option<char> {
+            if y < 0 || x < 0 || y >= height as i32 || x >= width as i32 {
+                None
+            } else {
+                Some(lines[y as usize + 1][x as usize + 1])
+            }
+        };
+        let position = |(y, x): (i32, i32)| (y as usize + 1, x as usize + 1);
+        let step_back = |(y, x): (i32, i32), direction: Direction| {
+            let (dy, dx) = Snake::advancement_to_add(&direction);
+            (y - dy, x - dx)
+        };
+
+        let mut body = vec![head];
+        let mut to = direction;
+        let mut current = step_back(head, direction);
+        if !glyph_at(current).map_or(false, is_body_glyph) {
+            return error(position(head), "the snake head has no body behind it");
+        }
+        loop {
+            let glyph = glyph_at(current).unwrap();
+            let from = Direction::ALL
+                .iter()
+                .cloned()
+                .filter(|from| *from != to.opposite())
+                .find(|from| Snake::get_body_glyph_from_directions(to, *from) == glyph);
+            let from = match from {
+                Some(from) => from,
+                None => {
+                    return error(
+                        position(current),
+                        "this segment doesn't join the one in front of it",
+                    )
+                }
+            };
+            body.push(current);
+            let next = step_back(current, from);
+            let continues = glyph_at(next).map_or(false, is_body_glyph) && !body.contains(&next);
+            if !continues {
+                if from != to {
+                    return error(position(current), "the snake's tail can't be a corner");
+                }
+                break;
+            }
+            to = from;
+            current = next;
+        }
+
+        for (y, line) in lines.iter().enumerate().skip(1).take(height) {
+            for (x, glyph) in line.iter().enumerate().skip(1).take(width) {
+                let location = ((y - 1) as i32, (x - 1) as i32);
+                if is_body_glyph(*glyph) && !body.contains(&location) {
+                    return error((y, x), "this segment isn't joined to the snake");
+                }
+            }
+        }
+        Ok(body)
+    }
+
+    /// Starts a new game on this board, with apple placement seeded by
+    /// `seed`. The game keeps as many apples on the board as the level
+    /// starts with, and at least one.
+    pub fn start(&self, seed: u64) -> Game {
+        let mut game = Game::new(self.width, self.height, &self.snake, seed);
+        for location in self.obstacles.iter() {
+            game.add_obstacle(*location);
+        }
+        for apple in self.apples.iter() {
+            game.add_apple(*apple);
+        }
+        game.apple_target = self.apples.len().max(1);
+        game
+    }
+}
+
+#[test]
+fn test_parsing_built_in_levels() {
+    let terminal = Level::parse(TERMINAL).unwrap();
+    assert_eq!((terminal.width, terminal.height), (20, 20));
+    assert_eq!(
+        terminal.snake,
+        vec![(10, 10), (10, 9), (10, 8), (10, 7), (10, 6)]
+    );
+    assert_eq!(
+        terminal.apples,
+        vec![Apple::new((4, 4)), Apple::new((9, 9)), Apple::new((9, 10))]
+    );
+    assert!(terminal.obstacles.is_empty());
+
+    let classic = Level::parse(CLASSIC).unwrap();
+    assert_eq!(
+        classic.snake,
+        vec![(10, 10), (10, 11), (10, 12), (10, 13), (10, 14)]
+    );
+    assert!(classic.apples.is_empty());
+
+    let arena = Level::parse(include_str!("../levels/arena.txt")).unwrap();
+    assert_eq!((arena.width, arena.height), (20, 20));
+    assert_eq!(arena.snake, vec![(10, 10), (10, 11), (10, 12), (10, 13)]);
+    assert_eq!(arena.apples, vec![Apple::new((7, 10))]);
+    assert_eq!(arena.obstacles.len(), 36);
+}
+
+#[test]
+fn test_parsing_round_trips_through_the_print_renderer() {
+    #[rustfmt::skip]
+    let text = concat!("------\n",
+                       "|#O $|\n",
+                       "|╔═╗ |\n",
+                       "|║ ╚>|\n",
+                       "|║  #|\n",
+                       "------\n");
+    let level = Level::parse(text).unwrap();
+    assert_eq!(
+        level.snake,
+        vec![(2, 3), (2, 2), (1, 2), (1, 1), (1, 0), (2, 0), (3, 0)]
+    );
+    let mut game = level.start(0);
+    let mut rendered = vec![vec![' '; level.width]; level.height];
+    let body: String = text
+        .lines()
+        .skip(1)
+        .take(4)
+        .fold(String::new(), |mut init, line| {
+            init.push_str(&line[1..line.len() - 1]);
+            init.push('\n');
+            init
+        });
+    assert_eq!(game.render_to_string(&mut rendered), body);
+}
+
+#[test]
+fn test_parse_errors() {
+    let error_at = |text: &str| {
+        let error = Level::parse(text).unwrap_err();
+        (error.line, error.column)
+    };
+    assert_eq!(error_at("----\n|<═|\n|  \n----\n"), (3, 4));
+    assert_eq!(error_at("----\n|<═|\n|x |\n----\n"), (3, 2));
+    assert_eq!(error_at("----\n|<═|\n|^║|\n----\n"), (3, 2));
+    assert_eq!(error_at("-----\n|<═ |\n|  ═|\n-----\n"), (3, 4));
+    assert_eq!(error_at("----\n|<║|\n----\n"), (2, 3));
+    assert_eq!(error_at("----\n|< |\n----\n"), (2, 2));
+    assert_eq!(error_at("----\n|  |\n----\n"), (1, 1));
+    assert_eq!(error_at("----\n|<═|\n"), (3, 1));
+    let error = Level::parse("-----\n|>  |\n\n-----").unwrap_err();
+    assert_eq!((error.line, error.column), (3, 1));
+    assert_eq!(error.message, "expected '|'");
+}
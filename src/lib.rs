@@ -0,0 +1,40 @@
+//! A headless snake simulation, along with the terminal and ggez front-ends
+//! that drive it.
+//!
+//! The engine lives in [`Game`], which owns a [`Snake`] and the [`Apple`]s it
+//! is chasing. Nothing in the simulation depends on a renderer, so a game can
+//! be stepped with [`Game::advance`] and inspected directly. The ggez
+//! front-end in `pretty_rendering` is only built with the default `graphics`
+//! feature.
+pub mod agent;
+pub mod apple;
+pub mod autopilot;
+pub mod clock;
+pub mod difficulty;
+pub mod environment;
+pub mod event;
+pub mod game;
+pub mod hamiltonian;
+pub mod history;
+pub mod level;
+pub mod observation;
+pub mod obstacle;
+pub mod occupancy;
+#[cfg(feature = "graphics")]
+pub mod pretty_rendering;
+pub mod print_rendering;
+pub mod replay;
+pub mod save;
+pub mod settings;
+pub mod snake;
+pub mod tournament;
+
+pub use crate::apple::Apple;
+pub use crate::difficulty::Difficulty;
+pub use crate::environment::{Action, Environment};
+pub use crate::event::{DeathCause, Event};
+pub use crate::game::Game;
+pub use crate::history::History;
+pub use crate::obstacle::Obstacle;
+pub use crate::replay::Replay;
+pub use crate::snake::{Direction, Snake};
@@ -0,0 +1,230 @@
+//! Ways of turning a `Game` into numbers for an agent to look at.
+//!
+//! Each encoder returns a flat `Vec<f32>`, with its layout documented on the
+//! encoder and every value between `0` and `1`. `grid`, `window` and
+//! `features` have the signature `Environment::observe` expects, so any of
+//! them can be plugged straight in.
+use crate::occupancy::Cell;
+use crate::snake::{Direction, Snake};
+use crate::Game;
+
+/// How many channels `grid` has.
+pub const GRID_CHANNELS: usize = 4;
+/// How many channels `window` has.
+pub const WINDOW_CHANNELS: usize = 3;
+/// How many cells `window` sees either side of the head.
+pub const WINDOW_RADIUS: i32 = 5;
+/// How many values `features` has.
+pub const FEATURES: usize = 11;
+
+/// The whole board as `GRID_CHANNELS` planes of `height` rows of `width`
+/// cells, so the value for `channel` at `(y, x)` is at
+/// `(channel * height + y) * width + x`.
+///
+/// | Channel | Value                                                        |
+/// |---------|--------------------------------------------------------------|
+/// | 0       | `1` on the head                                              |
+/// | 1       | The body, from just under `1` behind the head down to the    |
+/// |         | tail, which is `1 / length`                                  |
+/// | 2       | `1` on apples                                                |
+/// | 3       | `1` on obstacles                                             |
+pub fn grid(game: &Game) -> Vec<f32> {
+    let plane = game.width * game.height;
+    let mut observation = vec![0.0; GRID_CHANNELS * plane];
+    let offset = |(y, x): (i32, i32)| {
+        if y >= 0 && x >= 0 && (y as usize) < game.height && (x as usize) < game.width {
+            Some(y as usize * game.width + x as usize)
+        } else {
+            None
+        }
+    };
+    let length = game.snake.body.len();
+    for (index, location) in game.snake.body.iter().enumerate().skip(1) {
+        if let Some(offset) = offset(*location) {
+            observation[plane + offset] = body_age(index, length);
+        }
+    }
+    if let Some(offset) = offset(game.snake.body[0]) {
+        observation[offset] = 1.0;
+    }
+    for apple in game.apples.values() {
+        if let Some(offset) = offset(apple.location) {
+            observation[2 * plane + offset] = 1.0;
+        }
+    }
+    for obstacle in game.obstacles.iter() {
+        if let Some(offset) = offset(obstacle.location) {
+            observation[3 * plane + offset] = 1.0;
+        }
+    }
+    observation
+}
+
+/// `window_of` with a `WINDOW_RADIUS` cell radius.
+pub fn window(game: &Game) -> Vec<f32> {
+    window_of(game, WINDOW_RADIUS)
+}
+
+/// The square of cells up to `radius` away from the head, turned so the
+/// snake is heading up it. It's `WINDOW_CHANNELS` planes of `2 * radius + 1`
+/// rows, starting from the furthest ahead of the snake, each running from
+/// the snake's left to its right. The head is the centre cell.
+///
+/// | Channel | Value                                                        |
+/// |---------|--------------------------------------------------------------|
+/// | 0       | The body as in `grid`'s channel 1, including `1` on the head |
+/// | 1       | `1` on apples                                                |
+/// | 2       | `1` on obstacles and anywhere off the board                  |
+///
+/// A wrapping snake sees the cells it would come back in on rather than the
+/// edge of the board.
+pub fn window_of(game: &Game, radius: i32) -> Vec<f32> {
+    let size = (2 * radius + 1) as usize;
+    let plane = size * size;
+    let mut observation = vec![0.0; WINDOW_CHANNELS * plane];
+    let ages = board_ages(game);
+    let (head_y, head_x) = game.snake.body[0];
+    let (ahead_y, ahead_x) = Snake::advancement_to_add(&game.snake.direction);
+    let (right_y, right_x) = Snake::advancement_to_add(&game.snake.direction.clockwise());
+    for row in 0..size {
+        let ahead = radius - row as i32;
+        for column in 0..size {
+            let right = column as i32 - radius;
+            let location = game.snake.board_location((
+                head_y + ahead * ahead_y + right * right_y,
+                head_x + ahead * ahead_x + right * right_x,
+            ));
+            let offset = row * size + column;
+            match location.and_then(|location| game.occupancy().get(location)) {
+                Some(Cell::Snake) => {
+                    let (y, x) = location.unwrap();
+                    observation[offset] = ages[y as usize * game.width + x as usize];
+                }
+                Some(Cell::Apple) => observation[plane + offset] = 1.0,
+                Some(Cell::Obstacle) | None => observation[2 * plane + offset] = 1.0,
+                Some(Cell::Empty) => (),
+            }
+        }
+    }
+    observation
+}
+
+/// A handful of values summing up what's around the head, in this order:
+///
+/// | Index | Value                                                          |
+/// |-------|----------------------------------------------------------------|
+/// | 0-2   | `1` if moving straight on, left or right would kill the snake  |
+/// | 3-6   | `1` for the way the snake is heading: up, right, down or left  |
+/// | 7-10  | `1` if the nearest apple is anywhere ahead of, behind, left of |
+/// |       | or right of the head, as the snake sees it                     |
+pub fn features(game: &Game) -> Vec<f32> {
+    let mut observation = vec![0.0; FEATURES];
+    let heading = game.snake.direction;
+    let turns = [heading, heading.anticlockwise(), heading.clockwise()];
+    for (index, direction) in turns.iter().enumerate() {
+        if deadly(game, *direction) {
+            observation[index] = 1.0;
+        }
+    }
+    let heading_index = match heading {
+        Direction::Up => 0,
+        Direction::Right => 1,
+        Direction::Down => 2,
+        Direction::Left => 3,
+    };
+    observation[3 + heading_index] = 1.0;
+    let (head_y, head_x) = game.snake.body[0];
+    let nearest = game.apples.values().min_by_key(|apple| {
+        let (y, x) = apple.location;
+        (y - head_y).abs() + (x - head_x).abs()
+    });
+    if let Some(apple) = nearest {
+        let (offset_y, offset_x) = (apple.location.0 - head_y, apple.location.1 - head_x);
+        let (ahead_y, ahead_x) = Snake::advancement_to_add(&heading);
+        let (right_y, right_x) = Snake::advancement_to_add(&heading.clockwise());
+        let ahead = offset_y * ahead_y + offset_x * ahead_x;
+        let right = offset_y * right_y + offset_x * right_x;
+        for (index, side) in [ahead > 0, ahead < 0, right < 0, right > 0]
+            .iter()
+            .enumerate()
+        {
+            if *side {
+                observation[7 + index] = 1.0;
+            }
+        }
+    }
+    observation
+}
+
+/// How `grid` shows the body segment `index` cells behind the head.
+fn body_age(index: usize, length: usize) -> f32 {
+    (length - index) as f32 / length as f32
+}
+
+/// `grid`'s body channel, with `1` on the head too.
+fn board_ages(game: &Game) -> Vec<f32> {
+    let mut ages = vec![0.0; game.width * game.height];
+    let length = game.snake.body.len();
+    for (index, location) in game.snake.body.iter().enumerate().rev() {
+        if let Some((y, x)) = game.snake.board_location(*location) {
+            ages[y as usize * game.width + x as usize] = body_age(index, length);
+        }
+    }
+    ages
+}
+
+/// Whether moving the head one cell in `direction` would kill the snake. The
+/// tail is safe to move onto, since it moves out of the way, unless the
+/// snake is growing.
+fn deadly(game: &Game, direction: Direction) -> bool {
+    let location = match game.snake.step(game.snake.body[0], direction) {
+        Some(location) => location,
+        None => return true,
+    };
+    match game.occupancy().get(location) {
+        Some(Cell::Snake) => game.snake.growth > 0 || game.snake.body.back() != Some(&location),
+        Some(Cell::Obstacle) | None => true,
+        Some(Cell::Empty) | Some(Cell::Apple) => false,
+    }
+}
+
+#[test]
+fn test_grid_channels() {
+    let mut game = Game::new(4, 3, &[(1, 2), (1, 1), (1, 0)], 0);
+    game.add_apple(crate::Apple::new((0, 3)));
+    game.add_obstacle((2, 3));
+    let observation = grid(&game);
+    assert_eq!(observation.len(), GRID_CHANNELS * 12);
+    let at = |channel: usize, (y, x): (usize, usize)| observation[(channel * 3 + y) * 4 + x];
+    let nonzero = |channel: usize| {
+        (0..12)
+            .filter(|cell| observation[channel * 12 + cell] > 0.0)
+            .count()
+    };
+    assert!(at(0, (1, 2)) > 0.5 && nonzero(0) == 1);
+    assert!(at(1, (1, 1)) > at(1, (1, 0)) && nonzero(1) == 2);
+    assert!(at(2, (0, 3)) > 0.5 && nonzero(2) == 1);
+    assert!(at(3, (2, 3)) > 0.5 && nonzero(3) == 1);
+}
+
+#[test]
+fn test_window_turns_with_the_snake() {
+    // Heading down the left edge, with an apple to the snake's left.
+    let mut game = Game::new(5, 5, &[(2, 0), (1, 0), (0, 0)], 0);
+    game.add_apple(crate::Apple::new((2, 1)));
+    let observation = window_of(&game, 1);
+    assert_eq!(observation.len(), WINDOW_CHANNELS * 9);
+    let walls: Vec<u32> = observation[18..].iter().map(|cell| *cell as u32).collect();
+    assert_eq!(walls, vec![0, 0, 1, 0, 0, 1, 0, 0, 1]);
+    let apples: Vec<u32> = observation[9..18].iter().map(|cell| *cell as u32).collect();
+    assert_eq!(apples, vec![0, 0, 0, 1, 0, 0, 0, 0, 0]);
+    assert!(observation[4] > observation[7] && observation[7] > 0.0);
+}
+
+#[test]
+fn test_features() {
+    let mut game = Game::new(5, 5, &[(0, 2), (0, 1)], 0);
+    game.add_apple(crate::Apple::new((4, 0)));
+    let observation: Vec<u32> = features(&game).iter().map(|value| *value as u32).collect();
+    assert_eq!(observation, vec![0, 1, 0, 0, 1, 0, 0, 0, 1, 0, 1]);
+}
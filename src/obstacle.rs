@@ -0,0 +1,7 @@
+use serde::{Deserialize, Serialize};
+
+/// A static wall tile at `(y, x)` that kills the snake on contact.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub location: (i32, i32),
+}
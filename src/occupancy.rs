@@ -0,0 +1,186 @@
+use rand::Rng;
+
+/// What's covering a single cell of the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cell {
+    Empty,
+    Snake,
+    Apple,
+    Obstacle,
+}
+
+/// Which cells of a `width` by `height` board are taken, and by what.
+///
+/// Alongside the cells it keeps a count of the empty cells in a Fenwick tree,
+/// so looking a cell up is O(1) and changing it or picking a random empty
+/// cell is O(log n), however big the board or long the snake.
+///
+/// `random_empty` picks the nth empty cell in row order, so which one it
+/// picks only depends on which cells are empty. A grid built afresh from the
+/// same board picks the same cells as one that's been kept up to date.
+#[derive(Clone, Default)]
+pub struct Occupancy {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    /// `tree[i]` counts the empty cells among the `i & -i` cells ending
+    /// with offset `i - 1`.
+    tree: Vec<u32>,
+    empty: usize,
+}
+
+impl Occupancy {
+    /// Creates an occupancy grid from its cells, which are in row order.
+    pub fn from_cells(width: usize, height: usize, cells: Vec<Cell>) -> Occupancy {
+        let mut tree = vec![0; cells.len() + 1];
+        let mut empty = 0;
+        for (offset, cell) in cells.iter().enumerate() {
+            if *cell == Cell::Empty {
+                tree[offset + 1] += 1;
+                empty += 1;
+            }
+        }
+        for index in 1..tree.len() {
+            let parent = index + (index & index.wrapping_neg());
+            if parent < tree.len() {
+                tree[parent] += tree[index];
+            }
+        }
+        Occupancy {
+            width,
+            height,
+            cells,
+            tree,
+            empty,
+        }
+    }
+
+    fn offset(&self, (y, x): (i32, i32)) -> Option<usize> {
+        if y < 0 || x < 0 || y as usize >= self.height || x as usize >= self.width {
+            None
+        } else {
+            Some(y as usize * self.width + x as usize)
+        }
+    }
+
+    fn location(&self, offset: usize) -> (i32, i32) {
+        ((offset / self.width) as i32, (offset % self.width) as i32)
+    }
+
+    /// What's at `location`, or `None` if it's off the board.
+    pub fn get(&self, location: (i32, i32)) -> Option<Cell> {
+        self.offset(location).map(|offset| self.cells[offset])
+    }
+
+    /// Covers `location` with `cell`. Locations off the board are ignored.
+    pub fn set(&mut self, location: (i32, i32), cell: Cell) {
+        let offset = match self.offset(location) {
+            Some(offset) => offset,
+            None => return,
+        };
+        let was_empty = self.cells[offset] == Cell::Empty;
+        self.cells[offset] = cell;
+        if was_empty && cell != Cell::Empty {
+            self.empty -= 1;
+            self.update(offset, |count| count - 1);
+        } else if !was_empty && cell == Cell::Empty {
+            self.empty += 1;
+            self.update(offset, |count| count + 1);
+        }
+    }
+
+    /// Applies `change` to the count of every node of the tree covering
+    /// `offset`.
+    fn update(&mut self, offset: usize, change: impl Fn(u32) -> u32) {
+        let mut index = offset + 1;
+        while index < self.tree.len() {
+            self.tree[index] = change(self.tree[index]);
+            index += index & index.wrapping_neg();
+        }
+    }
+
+    /// How many cells are empty.
+    pub fn empty_cells(&self) -> usize {
+        self.empty
+    }
+
+    /// Every empty cell, in row order.
+    pub fn empty_locations<'a>(&'a self) -> impl Iterator<Item = (i32, i32)> + 'a {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| **cell == Cell::Empty)
+            .map(move |(offset, _)| self.location(offset))
+    }
+
+    /// The offset of the `nth` empty cell in row order, counting from zero.
+    /// There have to be more than `nth` empty cells.
+    fn nth_empty(&self, mut nth: u32) -> usize {
+        let mut index = 0;
+        let mut step = (self.tree.len() - 1).next_power_of_two();
+        while step > 0 {
+            if index + step < self.tree.len() && self.tree[index + step] <= nth {
+                index += step;
+                nth -= self.tree[index];
+            }
+            step /= 2;
+        }
+        index
+    }
+
+    /// A uniformly random empty cell, or `None` if the board is full.
+    pub fn random_empty<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(i32, i32)> {
+        if self.empty == 0 {
+            None
+        } else {
+            let nth = rng.gen_range(0, self.empty) as u32;
+            Some(self.location(self.nth_empty(nth)))
+        }
+    }
+}
+
+#[test]
+fn test_empty_cells_near_full_board() {
+    let mut occupancy = Occupancy::from_cells(3, 3, vec![Cell::Empty; 9]);
+    for location in [(0, 0), (0, 1), (0, 2), (1, 2), (1, 1)].iter() {
+        occupancy.set(*location, Cell::Snake);
+    }
+    occupancy.set((2, 2), Cell::Apple);
+    let empty: Vec<_> = occupancy.empty_locations().collect();
+    assert_eq!(empty, vec![(1, 0), (2, 0), (2, 1)]);
+
+    occupancy.set((0, 0), Cell::Empty);
+    occupancy.set((1, 0), Cell::Obstacle);
+    occupancy.set((2, 0), Cell::Snake);
+    occupancy.set((2, 1), Cell::Snake);
+    assert_eq!(occupancy.empty_cells(), 1);
+    let mut rng = rand::thread_rng();
+    assert_eq!(occupancy.random_empty(&mut rng), Some((0, 0)));
+    occupancy.set((0, 0), Cell::Snake);
+    assert_eq!(occupancy.random_empty(&mut rng), None);
+    assert_eq!(occupancy.get((3, 0)), None);
+}
+
+#[test]
+fn test_random_empty_ignores_history() {
+    let mut occupancy = Occupancy::from_cells(4, 3, vec![Cell::Empty; 12]);
+    for location in [(0, 0), (2, 3), (1, 1), (0, 2), (1, 3)].iter() {
+        occupancy.set(*location, Cell::Snake);
+    }
+    occupancy.set((1, 1), Cell::Empty);
+    let cells = (0..12)
+        .map(|offset| occupancy.get((offset / 4, offset % 4)).unwrap())
+        .collect();
+    let rebuilt = Occupancy::from_cells(4, 3, cells);
+    let empty: Vec<_> = occupancy.empty_locations().collect();
+    for (nth, location) in empty.iter().enumerate() {
+        assert_eq!(
+            occupancy.location(occupancy.nth_empty(nth as u32)),
+            *location
+        );
+        assert_eq!(
+            rebuilt.nth_empty(nth as u32),
+            occupancy.nth_empty(nth as u32)
+        );
+    }
+}
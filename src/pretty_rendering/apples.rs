@@ -1,18 +1,33 @@
 use crate::pretty_rendering::helpers::add_points;
 
+use crate::apple::AppleKind;
 use ggez::graphics::{mint, BlendMode, DrawMode, DrawParam, Drawable, Rect};
 use ggez::nalgebra as na;
 use ggez::{graphics, Context, GameResult};
-use std::collections::HashSet;
+
+/// How many ticks before it disappears an apple starts blinking.
+const BLINK_TICKS: u32 = 10;
+
+/// The colour each kind of apple is drawn in.
+fn colour(kind: AppleKind) -> graphics::Color {
+    match kind {
+        AppleKind::Normal => graphics::Color::new(1.0, 0.0, 0.0, 1.0),
+        AppleKind::Golden => graphics::Color::new(1.0, 0.85, 0.0, 1.0),
+        AppleKind::Feast => graphics::Color::new(1.0, 0.5, 0.0, 1.0),
+        AppleKind::Shrinking => graphics::Color::new(0.4, 0.8, 1.0, 1.0),
+        AppleKind::Speed => graphics::Color::new(1.0, 0.3, 1.0, 1.0),
+        AppleKind::Poison => graphics::Color::new(0.5, 1.0, 0.2, 1.0),
+    }
+}
 
 pub struct Apples<'a> {
-    inner: &'a HashSet<crate::Apple>,
+    inner: &'a crate::apple::Apples,
     container: Rect,
     confines: (i32, i32),
 }
 
 impl<'a> Apples<'a> {
-    pub fn new(inner: &'a HashSet<crate::Apple>, container: Rect, confines: (i32, i32)) -> Self {
+    pub fn new(inner: &'a crate::apple::Apples, container: Rect, confines: (i32, i32)) -> Self {
         Apples {
             inner,
             container,
@@ -23,15 +38,22 @@ impl<'a> Apples<'a> {
 
 impl<'a> Drawable for Apples<'a> {
     fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
-        let x_interval = self.container.w / self.confines.0 as f32;
-        let y_interval = self.container.h / self.confines.1 as f32;
+        let x_interval = self.container.w / self.confines.1 as f32;
+        let y_interval = self.container.h / self.confines.0 as f32;
         let draw_offsets: Vec<_> = self
             .inner
-            .iter()
-            .map(|apple| -> mint::Point2<f32> {
+            .values()
+            .filter(|apple| match apple.lifetime {
+                Some(ticks) => ticks > BLINK_TICKS || ticks % 2 == 1,
+                None => true,
+            })
+            .map(|apple| -> (mint::Point2<f32>, graphics::Color) {
                 let x = apple.location.1 as f32 * x_interval;
                 let y = apple.location.0 as f32 * y_interval;
-                add_points(na::Point2::new(x, y), param.dest)
+                (
+                    add_points(na::Point2::new(x, y), param.dest),
+                    colour(apple.kind),
+                )
             })
             .collect();
         let rect = graphics::Mesh::new_rectangle(
@@ -43,12 +65,12 @@ impl<'a> Drawable for Apples<'a> {
                 x_interval / 3.0,
                 y_interval / 3.0,
             ),
-            graphics::Color::new(1.0, 0.0, 0.0, 1.0),
+            graphics::WHITE,
         )
         .unwrap();
         draw_offsets
             .into_iter()
-            .map(|offset: mint::Point2<f32>| graphics::draw(ctx, &rect, (offset,)))
+            .map(|(offset, colour)| graphics::draw(ctx, &rect, (offset, colour)))
             .collect()
     }
 
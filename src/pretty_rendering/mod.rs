@@ -1,40 +1,193 @@
 pub mod apples;
 pub mod debug_mesh;
 mod helpers;
+pub mod obstacles;
 pub mod snake;
 
+use crate::autopilot;
+use crate::clock::Clock;
+use crate::history::{History, REWIND_TICKS};
+use crate::level::{self, Level};
 use crate::pretty_rendering::debug_mesh::DebugMesh;
-use crate::snake::{Direction, Snake};
+use crate::replay::{Player, Replay};
+use crate::settings::Settings;
+use crate::snake::Direction;
 use crate::{pretty_rendering, Game};
 use ggez::conf::WindowMode;
 use ggez::event;
 use ggez::event::quit;
 use ggez::graphics;
 use ggez::graphics::{DrawParam, Drawable};
-use ggez::input::keyboard::{KeyCode, KeyMods};
+use ggez::input::keyboard::{self, KeyCode, KeyMods};
 use ggez::nalgebra as na;
-use std::collections::{HashSet, VecDeque};
-use std::time::Instant;
+use ggez::timer;
+use std::collections::VecDeque;
+use std::path::Path;
 
 const DEBUG: bool = true;
-const SECONDS_BETWEEN_FRAMES: f32 = 0.2;
+/// Where F5 saves the game and F9 loads it from.
+const SAVE_FILE: &str = "snake-save.json";
+/// Held down to run the game backwards.
+const REWIND_KEY: KeyCode = KeyCode::Back;
+/// How far the left and right arrows seek through a replay.
+const SEEK_TICKS: usize = 20;
 
 struct MainState {
     window_size: (f32, f32),
     game: Game,
-    last_advance: Instant,
+    /// The snake's body before the last tick, to draw it moving from.
+    previous_body: VecDeque<(i32, i32)>,
+    /// Time that has passed but hasn't been simulated yet, and whether
+    /// it's paused.
+    clock: Clock,
+    /// Whether the snake is steering itself.
+    autopilot: bool,
+    /// Earlier states of `game`, to rewind to.
+    history: History,
+    /// A recording of `game` so far, unless it was loaded from a save or is
+    /// a replay being watched, neither of which can be recorded.
+    replay: Option<Replay>,
+    /// The replay being watched, if `game` is being played back rather than
+    /// played.
+    player: Option<Player>,
+    level: Level,
+    settings: Settings,
 }
 
 impl MainState {
-    fn new(window_size: (f32, f32)) -> ggez::GameResult<MainState> {
+    fn new(
+        window_size: (f32, f32),
+        level: Level,
+        settings: Settings,
+        game: Game,
+        replay: Option<Replay>,
+        mut player: Option<Player>,
+    ) -> ggez::GameResult<MainState> {
+        let game = fit_to_window(window_size, game);
+        if let Some(player) = &mut player {
+            player.replay.start = fit_to_window(window_size, player.replay.start.clone());
+        }
         let s = MainState {
             window_size,
-            game: get_starting_game(window_size),
-            last_advance: Instant::now(),
+            previous_body: game.snake.body.clone(),
+            replay,
+            game,
+            clock: Clock::new(),
+            autopilot: settings.autopilot,
+            history: History::new(REWIND_TICKS),
+            player,
+            level,
+            settings,
         };
         Ok(s)
     }
 
+    fn restart(&mut self) {
+        if let Some(player) = &self.player {
+            let game = player.replay.start.clone();
+            self.replace_game(game, None);
+            return;
+        }
+        match Replay::new(self.settings.setup(&self.level)) {
+            Ok(replay) => self.replace_game(replay.start.clone(), Some(replay)),
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+
+    /// Swaps in a different game, recorded by `replay` if there is one,
+    /// leaving nothing of the old one behind.
+    fn replace_game(&mut self, game: Game, replay: Option<Replay>) {
+        self.game = fit_to_window(self.window_size, game);
+        self.previous_body = self.game.snake.body.clone();
+        self.clock = Clock::new();
+        self.history.clear();
+        self.replay = replay;
+    }
+
+    /// Runs a single tick, played from the replay being watched if there is
+    /// one. Writes out the recording once the game is over, if `--record`
+    /// asked for it.
+    fn tick(&mut self) {
+        self.history.record(&self.game);
+        self.previous_body = self.game.snake.body.clone();
+        match &self.player {
+            Some(player) => {
+                player.replay.play_tick(&mut self.game);
+            }
+            None => {
+                if self.autopilot {
+                    autopilot::steer(&mut self.game);
+                }
+                let replay = match &mut self.replay {
+                    Some(replay) => replay,
+                    None => {
+                        self.game.advance();
+                        return;
+                    }
+                };
+                replay.advance(&mut self.game);
+                if let (true, Some(path)) = (self.game.over, &self.settings.record) {
+                    replay.claim(&self.game);
+                    match replay.save(path) {
+                        Ok(()) => println!("Replay written to {}", path.display()),
+                        Err(error) => eprintln!("{}", error),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether there's nothing left to play, because the game is over or
+    /// the replay being watched has run out.
+    fn finished(&self) -> bool {
+        self.game.over
+            || self
+                .player
+                .as_ref()
+                .map_or(false, |player| player.finished(&self.game))
+    }
+
+    /// How long the current tick lasts, sped up or slowed down when watching
+    /// a replay.
+    fn seconds_between_ticks(&self) -> f32 {
+        match &self.player {
+            Some(player) => player.seconds_between_ticks(&self.game),
+            None => self.game.seconds_between_ticks(),
+        }
+    }
+
+    /// Jumps the replay being watched by `ticks`, backwards if negative.
+    fn seek(&mut self, ticks: isize) {
+        if let Some(player) = &self.player {
+            let tick = player.replay.tick_of(&self.game) as isize + ticks;
+            player.seek(&mut self.game, tick.max(0) as usize);
+            self.previous_body = self.game.snake.body.clone();
+            self.clock.reset();
+            self.history.clear();
+        }
+    }
+
+    /// Goes back a tick, returning whether there was one to go back to.
+    fn untick(&mut self) -> bool {
+        match self.history.rewind() {
+            Some(earlier) => {
+                self.previous_body = self.game.snake.body.clone();
+                self.game = earlier;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// How far the game is through its current tick, from 0 to 1.
+    fn tick_progress(&self) -> f32 {
+        if self.finished() {
+            1.0
+        } else {
+            self.clock.progress(self.seconds_between_ticks())
+        }
+    }
+
     fn draw_border(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
         let (w, h) = self.window_size;
         let border = ggez::graphics::Rect::new(30.0, 30.0, w - 60.0, h - 60.0);
@@ -55,62 +208,130 @@ impl MainState {
 
     fn draw_score(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
         let text = ggez::graphics::Text::new(format!("{:03}", self.game.score));
-        graphics::draw(ctx, &text, (na::Point2::new(self.window_size.0 - 100.0, 0.0 as f32),))
+        graphics::draw(
+            ctx,
+            &text,
+            (na::Point2::new(self.window_size.0 - 100.0, 0.0 as f32),),
+        )
+    }
+
+    fn draw_speed(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let text = ggez::graphics::Text::new(format!("Speed {}", self.game.speed_level()));
+        graphics::draw(
+            ctx,
+            &text,
+            (na::Point2::new(self.window_size.0 - 200.0, 0.0 as f32),),
+        )
+    }
+
+    fn draw_autopilot(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        if !self.autopilot || self.player.is_some() {
+            return Ok(());
+        }
+        let text = ggez::graphics::Text::new("AUTOPILOT");
+        graphics::draw(
+            ctx,
+            &text,
+            (na::Point2::new(self.window_size.0 - 320.0, 0.0 as f32),),
+        )
+    }
+
+    fn draw_outcome(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let outcome = if self.game.won {
+            "BOARD CLEARED!"
+        } else if self.game.over {
+            "GAME OVER"
+        } else {
+            return Ok(());
+        };
+        let text = ggez::graphics::Text::new(format!(
+            "{} Press R to play again, or hold Backspace to rewind",
+            outcome
+        ));
+        graphics::draw(
+            ctx,
+            &text,
+            (na::Point2::new(30.0, self.window_size.1 - 25.0),),
+        )
+    }
+
+    fn draw_paused(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        if !self.clock.paused() {
+            return Ok(());
+        }
+        let (w, h) = self.window_size;
+        let shade = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(30.0, 30.0, w - 60.0, h - 60.0),
+            graphics::Color::new(0.0, 0.0, 0.0, 0.5),
+        )?;
+        graphics::draw(ctx, &shade, (na::Point2::new(0.0, 0.0),))?;
+        let text = ggez::graphics::Text::new("PAUSED  P resumes, N steps one tick");
+        graphics::draw(ctx, &text, (na::Point2::new(w / 2.0 - 140.0, h / 2.0),))
+    }
+
+    fn draw_replay(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let player = match &self.player {
+            Some(player) => player,
+            None => return Ok(()),
+        };
+        let text = ggez::graphics::Text::new(format!(
+            "REPLAY {}/{} x{}  Left/Right seek, Up/Down change speed",
+            player.replay.tick_of(&self.game),
+            player.replay.len(),
+            player.speed
+        ));
+        graphics::draw(
+            ctx,
+            &text,
+            (na::Point2::new(30.0, self.window_size.1 - 25.0),),
+        )
+    }
+
+    fn draw_seed(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let text = ggez::graphics::Text::new(format!("Seed: {}", self.game.seed));
+        graphics::draw(ctx, &text, (na::Point2::new(30.0, 0.0 as f32),))
     }
 }
 
-fn get_snake_direction_from_keypress(
-    input: KeyCode,
-    current_direction: Direction,
-) -> Option<Direction> {
+fn get_snake_direction_from_keypress(input: KeyCode) -> Option<Direction> {
     match input {
-        KeyCode::Up => match current_direction {
-            Direction::Down => None,
-            _ => Some(Direction::Up),
-        },
-        KeyCode::Right => match current_direction {
-            Direction::Left => None,
-            _ => Some(Direction::Right),
-        },
-        KeyCode::Down => match current_direction {
-            Direction::Up => None,
-            _ => Some(Direction::Down),
-        },
-        KeyCode::Left => match current_direction {
-            Direction::Right => None,
-            _ => Some(Direction::Left),
-        },
+        KeyCode::Up => Some(Direction::Up),
+        KeyCode::Right => Some(Direction::Right),
+        KeyCode::Down => Some(Direction::Down),
+        KeyCode::Left => Some(Direction::Left),
         _ => None,
     }
 }
 
-fn get_starting_game(window_size: (f32, f32)) -> Game {
-    let mut game = Game {
-        over: false,
-        snake: Snake {
-            direction: Direction::Left,
-            lengthening: false,
-            body: VecDeque::from(vec![(10, 10), (10, 11), (10, 12), (10, 13), (10, 14)]),
-            confines: (20, 20),
-            confines_size: (window_size.0 - 60.0, window_size.1 - 60.0),
-        },
-        apples: HashSet::new(),
-        width: 20,
-        height: 20,
-        score: 0,
-    };
-    game.add_new_apple();
+fn fit_to_window(window_size: (f32, f32), mut game: Game) -> Game {
+    game.snake.confines_size = (window_size.0 - 60.0, window_size.1 - 60.0);
     game
 }
 
 impl event::EventHandler for MainState {
-    fn update(&mut self, _ctx: &mut ggez::Context) -> ggez::GameResult {
-        if self.last_advance.elapsed().as_secs_f32() >= SECONDS_BETWEEN_FRAMES && !self.game.over {
-            self.game.advance();
-            if self.game.snake.dead() {
-                self.game.over = true;
+    /// Runs however many fixed-length ticks fit in the time since the last
+    /// update, carrying any remainder over to the next one. While the rewind
+    /// key is held the ticks run backwards instead, even when paused or once
+    /// the game is over.
+    fn update(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let rewinding = keyboard::is_key_pressed(ctx, REWIND_KEY) && !self.history.is_empty();
+        if !rewinding && self.finished() {
+            return Ok(());
+        }
+        self.clock.pass(timer::delta(ctx).as_secs_f32(), rewinding);
+        while self.clock.next_tick(self.seconds_between_ticks()) {
+            let ticked = if rewinding {
+                self.untick()
+            } else {
+                self.tick();
+                !self.finished()
+            };
+            if !ticked {
+                self.clock.reset();
+                break;
             }
-            self.last_advance = Instant::now();
         }
         Ok(())
     }
@@ -119,9 +340,18 @@ impl event::EventHandler for MainState {
         let play_area = na::Point2::new(30.0, 30.0);
         let container = graphics::Rect::new(30.0, 30.0, 540.0, 540.0);
         graphics::clear(ctx, [0.1, 0.2, 0.3, 1.0].into());
-        self.game
-            .snake
-            .draw(ctx, DrawParam::default().dest(play_area))?;
+        let obstacles = pretty_rendering::obstacles::Obstacles::new(
+            &self.game.obstacles,
+            container,
+            self.game.snake.confines,
+        );
+        obstacles.draw(ctx, DrawParam::default().dest(play_area))?;
+        let snake = pretty_rendering::snake::MovingSnake {
+            snake: &self.game.snake,
+            previous: &self.previous_body,
+            progress: self.tick_progress(),
+        };
+        snake.draw(ctx, DrawParam::default().dest(play_area))?;
         let apples = pretty_rendering::apples::Apples::new(
             &self.game.apples,
             container,
@@ -131,6 +361,15 @@ impl event::EventHandler for MainState {
 
         self.draw_border(ctx)?;
         self.draw_score(ctx)?;
+        self.draw_speed(ctx)?;
+        self.draw_autopilot(ctx)?;
+        self.draw_seed(ctx)?;
+        if self.player.is_some() {
+            self.draw_replay(ctx)?;
+        } else {
+            self.draw_outcome(ctx)?;
+        }
+        self.draw_paused(ctx)?;
         if DEBUG {
             let mesh = DebugMesh {
                 rows: self.game.snake.confines.0 as usize,
@@ -151,15 +390,44 @@ impl event::EventHandler for MainState {
         _repeat: bool,
     ) {
         match keycode {
+            KeyCode::Left if self.player.is_some() => self.seek(-(SEEK_TICKS as isize)),
+            KeyCode::Right if self.player.is_some() => self.seek(SEEK_TICKS as isize),
+            KeyCode::Up | KeyCode::Down if self.player.is_some() => {
+                if let Some(player) = &mut self.player {
+                    player.change_speed(if keycode == KeyCode::Up { 2.0 } else { 0.5 });
+                }
+            }
             KeyCode::Up | KeyCode::Right | KeyCode::Down | KeyCode::Left => {
-                if let Some(new_dir) =
-                    get_snake_direction_from_keypress(keycode, self.game.snake.direction)
-                {
-                    self.game.snake.direction = new_dir;
+                if let Some(new_dir) = get_snake_direction_from_keypress(keycode) {
+                    self.game.snake.turn(new_dir);
                 }
             }
             KeyCode::R => {
-                self.game = get_starting_game(self.window_size);
+                self.restart();
+            }
+            KeyCode::A if self.player.is_none() => {
+                self.autopilot = !self.autopilot;
+            }
+            KeyCode::F5 => match self.game.save(Path::new(SAVE_FILE)) {
+                Ok(()) => println!("Saved to {}", SAVE_FILE),
+                Err(error) => eprintln!("{}", error),
+            },
+            KeyCode::F9 if self.player.is_none() => match Game::load(Path::new(SAVE_FILE)) {
+                Ok(game) => {
+                    if self.settings.record.is_some() {
+                        eprintln!("A loaded game can't be recorded");
+                    }
+                    self.replace_game(game, None);
+                }
+                Err(error) => eprintln!("{}", error),
+            },
+            KeyCode::P if !self.finished() => {
+                self.clock.toggle_pause();
+            }
+            KeyCode::N if !self.finished() => {
+                if self.clock.step() {
+                    self.tick();
+                }
             }
             KeyCode::Escape => {
                 quit(ctx);
@@ -169,7 +437,32 @@ impl event::EventHandler for MainState {
     }
 }
 
-pub fn ggez_main() -> ggez::GameResult {
+/// Opens a window and plays a game with the arrow keys, or with the
+/// autopilot, which A switches on and off. F5 saves the game and F9 loads it
+/// back. With `--replay` it plays back a replay instead.
+pub fn ggez_main(settings: Settings) -> ggez::GameResult {
+    let level = settings
+        .level(level::CLASSIC)
+        .map_err(ggez::GameError::ResourceLoadError)?;
+    let player = match &settings.replay {
+        Some(path) => Some(Player::new(
+            Replay::load(path).map_err(ggez::GameError::ResourceLoadError)?,
+        )),
+        None => None,
+    };
+    let replay = match (&player, &settings.load) {
+        (None, None) => {
+            Some(Replay::new(settings.setup(&level)).map_err(ggez::GameError::ResourceLoadError)?)
+        }
+        _ => None,
+    };
+    let game = match (&player, &replay) {
+        (Some(player), _) => player.replay.start.clone(),
+        (None, Some(replay)) => replay.start.clone(),
+        (None, None) => settings
+            .starting_game(&level)
+            .map_err(ggez::GameError::ResourceLoadError)?,
+    };
     let window_size = (600.0, 600.0);
     let cb = ggez::ContextBuilder::new("snakin'", "Rups").window_mode(WindowMode {
         width: window_size.0,
@@ -177,6 +470,6 @@ pub fn ggez_main() -> ggez::GameResult {
         ..Default::default()
     });
     let (ctx, event_loop) = &mut cb.build()?;
-    let state = &mut MainState::new(window_size)?;
+    let state = &mut MainState::new(window_size, level, settings, game, replay, player)?;
     event::run(ctx, event_loop, state)
 }
@@ -3,15 +3,67 @@ use crate::snake::{Direction, Snake};
 use ggez::graphics::{mint, BlendMode, DrawParam, Drawable, Rect};
 use ggez::nalgebra as na;
 use ggez::{graphics, Context, GameResult};
+use std::collections::VecDeque;
 use std::f32::consts::PI;
 
-impl Drawable for Snake {
+/// A snake drawn partway between where it was before the last tick and where
+/// it is now, so it glides from cell to cell instead of jumping.
+pub struct MovingSnake<'a> {
+    pub snake: &'a Snake,
+    /// The body as it was before the last tick.
+    pub previous: &'a VecDeque<(i32, i32)>,
+    /// How far through the current tick to draw it, from 0 to 1.
+    pub progress: f32,
+}
+
+impl<'a> MovingSnake<'a> {
+    /// Where each segment is drawn. Segments that have just appeared, or
+    /// that jumped across a wrapped edge, are drawn in their new cell.
+    fn points(&self) -> Vec<(f32, f32)> {
+        self.snake
+            .body
+            .iter()
+            .enumerate()
+            .map(|(index, &now)| match self.previous.get(index) {
+                Some(&then) if (now.0 - then.0).abs() + (now.1 - then.1).abs() <= 1 => (
+                    then.0 as f32 + (now.0 - then.0) as f32 * self.progress,
+                    then.1 as f32 + (now.1 - then.1) as f32 * self.progress,
+                ),
+                _ => tuple_to_f32(&now),
+            })
+            .collect()
+    }
+}
+
+impl<'a> Drawable for MovingSnake<'a> {
     fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
-        self.draw_head(ctx, param)?;
-        self.draw_body(ctx, param)?;
+        let points = self.points();
+        self.snake.draw_head(ctx, param, points[0])?;
+        self.snake.draw_body(ctx, param, &points)?;
         Ok(())
     }
 
+    fn dimensions(&self, ctx: &mut Context) -> Option<Rect> {
+        self.snake.dimensions(ctx)
+    }
+
+    fn set_blend_mode(&mut self, _mode: Option<BlendMode>) {}
+
+    fn blend_mode(&self) -> Option<BlendMode> {
+        None
+    }
+}
+
+impl Drawable for Snake {
+    fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
+        MovingSnake {
+            snake: self,
+            previous: &self.body,
+            progress: 1.0,
+        }
+        .draw(ctx, param)
+    }
+
     /// Returns a bounding box in the form of a `Rect`.
     ///
     /// It returns `Option` because some `Drawable`s may have no bounding box
@@ -33,11 +85,13 @@ impl Drawable for Snake {
 }
 
 impl Snake {
-    fn draw_head(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
-        let x_interval = self.confines_size.0 / self.confines.0 as f32;
-        let y_interval = self.confines_size.1 / self.confines.1 as f32;
-        let head_pos = self.body[0];
-        let (head_y, head_x) = tuple_to_f32(&head_pos);
+    fn draw_head(
+        &self,
+        ctx: &mut Context,
+        param: DrawParam,
+        (head_y, head_x): (f32, f32),
+    ) -> GameResult {
+        let (x_interval, y_interval) = self.intervals();
         // Line first:
         let line_start = (0.0, y_interval / 2.0);
         let line_end = (x_interval / 4.0, y_interval / 2.0);
@@ -61,7 +115,7 @@ impl Snake {
             ],
             graphics::Color::new(0.0, 1.0, 0.0, 1.0),
         )?;
-        let head_rotation = match Snake::head_direction(self.body.iter()) {
+        let head_rotation = match self.direction {
             Direction::Up => 3.0 * PI / 2.0,
             Direction::Right => 0.0,
             Direction::Down => PI / 2.0,
@@ -83,30 +137,58 @@ impl Snake {
     }
 
     fn body_pos_to_na_point(
-        (y, x): (i32, i32),
+        (y, x): (f32, f32),
         x_interval: f32,
         y_interval: f32,
     ) -> na::Point2<f32> {
         na::Point2::new(
-            x as f32 * x_interval + x_interval / 2.0,
-            y as f32 * y_interval + y_interval / 2.0,
+            x * x_interval + x_interval / 2.0,
+            y * y_interval + y_interval / 2.0,
         )
     }
 
-    fn draw_body(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
-        let x_interval = self.confines_size.0 / self.confines.0 as f32;
-        let y_interval = self.confines_size.0 / self.confines.1 as f32;
-        let body_points: Vec<_> = self
-            .body
-            .iter()
-            .map(|x| Snake::body_pos_to_na_point(*x, x_interval, y_interval))
-            .collect();
-        let body = graphics::Mesh::new_line(
-            ctx,
-            &body_points,
-            x_interval / 4.0,
-            graphics::Color::new(0.0, 1.0, 0.0, 1.0),
-        )?;
-        graphics::draw(ctx, &body, (param.dest,))
+    fn draw_body(&self, ctx: &mut Context, param: DrawParam, points: &[(f32, f32)]) -> GameResult {
+        let (x_interval, y_interval) = self.intervals();
+        for run in Snake::body_runs(points) {
+            let body_points: Vec<_> = run
+                .iter()
+                .map(|x| Snake::body_pos_to_na_point(*x, x_interval, y_interval))
+                .collect();
+            let body = graphics::Mesh::new_line(
+                ctx,
+                &body_points,
+                x_interval / 4.0,
+                graphics::Color::new(0.0, 1.0, 0.0, 1.0),
+            )?;
+            graphics::draw(ctx, &body, (param.dest,))?;
+        }
+        Ok(())
+    }
+
+    /// Splits the body's points into runs of neighbouring cells, so that a
+    /// wrapped snake isn't drawn with a line straight across the board. Each
+    /// run is extended by half a cell to the edge it wraps over.
+    fn body_runs(points: &[(f32, f32)]) -> Vec<Vec<(f32, f32)>> {
+        let mut runs = vec![];
+        let mut run = vec![points[0]];
+        for (&now, &then) in points.iter().zip(points.iter().skip(1)) {
+            let (dy, dx) = (then.0 - now.0, then.1 - now.1);
+            if dy.abs() > 1.5 || dx.abs() > 1.5 {
+                let half_step = |delta: f32| {
+                    if delta.abs() > 1.5 {
+                        -delta.signum() / 2.0
+                    } else {
+                        0.0
+                    }
+                };
+                let (dy, dx) = (half_step(dy), half_step(dx));
+                run.push((now.0 + dy, now.1 + dx));
+                runs.push(run);
+                run = vec![(then.0 - dy, then.1 - dx)];
+            }
+            run.push(then);
+        }
+        runs.push(run);
+        runs
     }
 }
@@ -1,10 +1,19 @@
 pub mod printable_apples;
 pub mod printable_game;
+pub mod printable_obstacles;
 pub mod printable_snake;
 
-use crate::{Apple, Game};
-use std::collections::HashSet;
-use std::io::stdin;
+use crate::autopilot;
+use crate::event::Event;
+use crate::history::{History, REWIND_TICKS};
+use crate::level;
+use crate::replay::{Player, Replay};
+use crate::settings::Settings;
+use crate::Game;
+use std::io::{stdin, BufRead};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
 
 use crate::snake::Direction;
 
@@ -15,15 +24,42 @@ pub trait Printable {
     fn print(&mut self, rendered: &mut Vec<Vec<char>>);
 }
 
-pub fn stringy_main() -> Result<(), ()> {
-    let mut game = Game::new(20, 20, &[(10, 10), (10, 9), (10, 8), (10, 7), (10, 6)]);
-    game.apples = HashSet::new();
-    game.apples.insert(Apple { location: (4, 4) });
-    game.apples.insert(Apple { location: (9, 9) });
-    game.apples.insert(Apple { location: (9, 10) });
-
-    loop_game(game);
-    #[allow(unreachable_code)]
+/// Plays a game in the terminal, reading one `wasd` move per line. A line
+/// with `p` pauses or resumes, and while paused only a line with `n` steps
+/// the game on. Each `b` rewinds a tick, even once the game is over.
+///
+/// With `--replay` it plays back a replay instead, and with `--record` it
+/// writes one once the game is over. With `--autopilot` the snake steers
+/// itself, and each line just moves the game on.
+pub fn stringy_main(settings: Settings) -> Result<(), ()> {
+    if let Some(path) = &settings.replay {
+        let replay = Replay::load(path).map_err(|error| eprintln!("{}", error))?;
+        watch_replay(Player::new(replay));
+        return Ok(());
+    }
+    let level = settings
+        .level(level::TERMINAL)
+        .map_err(|error| eprintln!("{}", error))?;
+    let replay = match &settings.load {
+        Some(_) => None,
+        None => Some(Replay::new(settings.setup(&level)).map_err(|error| eprintln!("{}", error))?),
+    };
+    let game = match &replay {
+        Some(replay) => replay.start.clone(),
+        None => settings
+            .starting_game(&level)
+            .map_err(|error| eprintln!("{}", error))?,
+    };
+    let (game, replay) = loop_game(game, replay, settings.autopilot);
+    match (settings.record, replay) {
+        (Some(path), Some(mut replay)) => {
+            replay.claim(&game);
+            replay.save(&path).map_err(|error| eprintln!("{}", error))?;
+            println!("Replay written to {}", path.display());
+        }
+        (Some(_), None) => eprintln!("A loaded game can't be recorded"),
+        (None, _) => (),
+    }
     Ok(())
 }
 
@@ -37,19 +73,148 @@ fn get_snake_direction_from_input(input: char) -> Option<Direction> {
     }
 }
 
-fn loop_game(mut game: Game) -> ! {
+/// Plays `game` until it's over or the input runs out, returning how it
+/// ended up along with `replay`, which records it if there is one. With
+/// `autopilot` set, the snake ignores the moves typed in and steers itself.
+fn loop_game(
+    mut game: Game,
+    mut replay: Option<Replay>,
+    autopilot: bool,
+) -> (Game, Option<Replay>) {
     let mut line = String::new();
     let mut rendered = vec![vec![' '; game.width]; game.height];
+    let mut paused = false;
+    let mut history = History::new(REWIND_TICKS);
     loop {
         game.print(&mut rendered);
-        let _ = stdin().read_line(&mut line).unwrap();
-        if !line.is_empty() {
-            if let Some(direction) = get_snake_direction_from_input(line.chars().next().unwrap()) {
-                game.snake.direction = direction;
+        println!(
+            "Score: {}  Speed: {} ({:.2}s a tick)",
+            game.score,
+            game.speed_level(),
+            game.seconds_between_ticks()
+        );
+        if game.over {
+            println!("b rewinds a tick, anything else quits");
+        } else if paused {
+            println!("PAUSED: p resumes, n steps one tick");
+        }
+        if stdin().read_line(&mut line).unwrap() == 0 {
+            return (game, replay);
+        }
+        let rewinds = line.matches('b').count();
+        if game.over && rewinds == 0 {
+            return (game, replay);
+        }
+        if rewinds > 0 {
+            for earlier in (0..rewinds).filter_map(|_| history.rewind()) {
+                game = earlier;
             }
             line.clear();
+            continue;
+        }
+        for direction in line.chars().filter_map(get_snake_direction_from_input) {
+            game.snake.turn(direction);
+        }
+        if line.contains('p') {
+            paused = !paused;
+        }
+        let step = line.contains('n');
+        line.clear();
+        if paused && !step {
+            continue;
+        }
+        if autopilot {
+            autopilot::steer(&mut game);
+        }
+        history.record(&game);
+        let events = match &mut replay {
+            Some(replay) => replay.advance(&mut game),
+            None => game.advance(),
+        };
+        for event in events {
+            match event {
+                Event::Died(cause) => {
+                    println!("Game over, the snake {}! Score: {}", cause, game.score)
+                }
+                Event::BoardFull => println!("Board cleared! Score: {}", game.score),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Plays a replay back, reading commands a line at a time while it plays:
+/// `p` plays or pauses, `n` steps a tick, `b` steps back a tick, `g N` goes
+/// to tick `N`, `+` and `-` change the speed and `q` quits.
+fn watch_replay(mut player: Player) {
+    let (sender, commands) = mpsc::channel();
+    thread::spawn(move || {
+        let stdin = stdin();
+        for line in stdin.lock().lines() {
+            let sent = line.map(|line| sender.send(line));
+            if !matches!(sent, Ok(Ok(()))) {
+                return;
+            }
+        }
+    });
+    let mut game = player.replay.start.clone();
+    let mut rendered = vec![vec![' '; game.width]; game.height];
+    let mut playing = true;
+    println!("p plays or pauses, n steps, b steps back, g N goes to tick N, + and - change speed, q quits");
+    loop {
+        game.print(&mut rendered);
+        let tick = player.replay.tick_of(&game);
+        println!(
+            "Replay tick {}/{}  x{}  Score: {}{}",
+            tick,
+            player.replay.len(),
+            player.speed,
+            game.score,
+            if playing { "" } else { "  PAUSED" }
+        );
+        let command = if playing && !player.finished(&game) {
+            let interval = Duration::from_secs_f32(player.seconds_between_ticks(&game));
+            match commands.recv_timeout(interval) {
+                Ok(line) => Some(line),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(interval);
+                    None
+                }
+            }
+        } else {
+            match commands.recv() {
+                Ok(line) => Some(line),
+                Err(_) => return,
+            }
+        };
+        let command = match command {
+            Some(command) => command,
+            None => {
+                player.replay.play_tick(&mut game);
+                continue;
+            }
+        };
+        let mut words = command.split_whitespace();
+        match words.next() {
+            Some("p") => playing = !playing,
+            Some("n") => {
+                playing = false;
+                player.replay.play_tick(&mut game);
+            }
+            Some("b") => {
+                playing = false;
+                player.seek(&mut game, tick.saturating_sub(1));
+            }
+            Some("g") => match words.next().map(str::parse) {
+                Some(Ok(tick)) => player.seek(&mut game, tick),
+                _ => println!("g expects a tick number"),
+            },
+            Some("+") => player.change_speed(2.0),
+            Some("-") => player.change_speed(0.5),
+            Some("q") => return,
+            _ => (),
         }
-        game.advance();
     }
 }
 
@@ -59,19 +224,44 @@ fn test_direction() {
     let downwards_body = vec![(11, 10), (10, 10)];
     let rightwards_body = vec![(10, 11), (10, 10)];
     let leftwards_body = vec![(10, 9), (10, 10)];
-    assert_eq!(Snake::head_direction(upwards_body.iter()), Direction::Up);
+    let wrapped_body = vec![(10, 19), (10, 0)];
+    let confines = (20, 20);
     assert_eq!(
-        Snake::head_direction(downwards_body.iter()),
+        Snake::head_direction(upwards_body.iter(), confines, false),
+        Direction::Up
+    );
+    assert_eq!(
+        Snake::head_direction(downwards_body.iter(), confines, false),
         Direction::Down
     );
     assert_eq!(
-        Snake::head_direction(rightwards_body.iter()),
+        Snake::head_direction(rightwards_body.iter(), confines, false),
         Direction::Right
     );
     assert_eq!(
-        Snake::head_direction(leftwards_body.iter()),
+        Snake::head_direction(leftwards_body.iter(), confines, false),
         Direction::Left
     );
+    assert_eq!(
+        Snake::head_direction(wrapped_body.iter(), confines, true),
+        Direction::Left
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_no_direction_across_a_gap_without_wrapping() {
+    Snake::direction((10, 19), (10, 0), (20, 20), false);
+}
+
+#[test]
+fn test_drawing_a_snake_that_wrapped_on_a_narrow_board() {
+    let mut game = Game::new(2, 1, &[(0, 0), (0, 1)], 0);
+    game.snake.wraps = true;
+    game.snake.advance();
+    assert_eq!(game.snake.body, vec![(0, 1), (0, 0)]);
+    let mut render_to = vec![vec![' '; 2]; 1];
+    assert_eq!(game.render_to_string(&mut render_to), "═<\n");
 }
 
 #[test]
@@ -94,26 +284,26 @@ fn test_drawing_simple_snakes() {
         let correct_downwards = concat!(" ║ \n",
     " ║ \n",
     " v \n");
-    let mut upwards_game = Game::new(default_width, default_height, &[(0, 1), (1, 1), (2, 1)]);
+    let mut upwards_game = Game::new(default_width, default_height, &[(0, 1), (1, 1), (2, 1)], 0);
     assert_eq!(
         upwards_game.render_to_string(&mut render_to),
         correct_upwards
     );
 
     let mut correct_rightwards_game =
-        Game::new(default_width, default_height, &[(1, 2), (1, 1), (1, 0)]);
+        Game::new(default_width, default_height, &[(1, 2), (1, 1), (1, 0)], 0);
     assert_eq!(
         correct_rightwards_game.render_to_string(&mut render_to),
         correct_rightwards
     );
 
-    let mut leftwards_game = Game::new(default_width, default_height, &[(1, 0), (1, 1), (1, 2)]);
+    let mut leftwards_game = Game::new(default_width, default_height, &[(1, 0), (1, 1), (1, 2)], 0);
     assert_eq!(
         leftwards_game.render_to_string(&mut render_to),
         correct_leftwards
     );
 
-    let mut downwards_game = Game::new(default_width, default_height, &[(2, 1), (1, 1), (0, 1)]);
+    let mut downwards_game = Game::new(default_width, default_height, &[(2, 1), (1, 1), (0, 1)], 0);
     assert_eq!(
         downwards_game.render_to_string(&mut render_to),
         correct_downwards
@@ -129,7 +319,7 @@ fn test_drawing_turning_snakes() {
                                               " ║ \n",
                                               " ║ \n");
     let up_rightwards_body = vec![(0, 2), (0, 1), (1, 1), (2, 1)];
-    let mut up_rightwards_game = Game::new(default_width, default_height, &up_rightwards_body);
+    let mut up_rightwards_game = Game::new(default_width, default_height, &up_rightwards_body, 0);
     assert_eq!(
         up_rightwards_game.render_to_string(&mut render_to),
         correct_up_rightwards
@@ -139,7 +329,7 @@ fn test_drawing_turning_snakes() {
                                              " ║ \n",
                                              " ║ \n");
     let up_leftwards_body = vec![(0, 0), (0, 1), (1, 1), (2, 1)];
-    let mut up_leftwards_game = Game::new(default_width, default_height, &up_leftwards_body);
+    let mut up_leftwards_game = Game::new(default_width, default_height, &up_leftwards_body, 0);
     assert_eq!(
         up_leftwards_game.render_to_string(&mut render_to),
         correct_up_leftwards
@@ -149,7 +339,7 @@ fn test_drawing_turning_snakes() {
                                               "══╝\n",
                                               "   \n");
     let right_upwards_body = vec![(0, 2), (1, 2), (1, 1), (1, 0)];
-    let mut right_upwards_game = Game::new(default_width, default_height, &right_upwards_body);
+    let mut right_upwards_game = Game::new(default_width, default_height, &right_upwards_body, 0);
     assert_eq!(
         right_upwards_game.render_to_string(&mut render_to),
         correct_right_upwards
@@ -159,7 +349,8 @@ fn test_drawing_turning_snakes() {
                                                 "══╗\n",
                                                 "  v\n");
     let right_downwards_body = vec![(2, 2), (1, 2), (1, 1), (1, 0)];
-    let mut right_downwards_game = Game::new(default_width, default_height, &right_downwards_body);
+    let mut right_downwards_game =
+        Game::new(default_width, default_height, &right_downwards_body, 0);
     assert_eq!(
         right_downwards_game.render_to_string(&mut render_to),
         correct_right_downwards
@@ -169,7 +360,7 @@ fn test_drawing_turning_snakes() {
                                              "╚══\n",
                                              "   \n");
     let left_upwards_body = vec![(0, 0), (1, 0), (1, 1), (1, 2)];
-    let mut left_upwards_game = Game::new(default_width, default_height, &left_upwards_body);
+    let mut left_upwards_game = Game::new(default_width, default_height, &left_upwards_body, 0);
     assert_eq!(
         left_upwards_game.render_to_string(&mut render_to),
         correct_left_upwards
@@ -179,7 +370,7 @@ fn test_drawing_turning_snakes() {
                                                "╔══\n",
                                                "v  \n");
     let left_downwards_body = vec![(2, 0), (1, 0), (1, 1), (1, 2)];
-    let mut left_downwards_game = Game::new(default_width, default_height, &left_downwards_body);
+    let mut left_downwards_game = Game::new(default_width, default_height, &left_downwards_body, 0);
     assert_eq!(
         left_downwards_game.render_to_string(&mut render_to),
         correct_left_downwards
@@ -189,7 +380,7 @@ fn test_drawing_turning_snakes() {
                                                " ║ \n",
                                                "<╝ \n");
     let down_leftwards_body = vec![(2, 0), (2, 1), (1, 1), (0, 1)];
-    let mut down_leftwards_game = Game::new(default_width, default_height, &down_leftwards_body);
+    let mut down_leftwards_game = Game::new(default_width, default_height, &down_leftwards_body, 0);
     assert_eq!(
         down_leftwards_game.render_to_string(&mut render_to),
         correct_down_leftwards
@@ -199,9 +390,92 @@ fn test_drawing_turning_snakes() {
                                                 " ║ \n",
                                                 " ╚>\n");
     let down_rightwards_body = vec![(2, 2), (2, 1), (1, 1), (0, 1)];
-    let mut down_rightwards_game = Game::new(default_width, default_height, &down_rightwards_body);
+    let mut down_rightwards_game =
+        Game::new(default_width, default_height, &down_rightwards_body, 0);
     assert_eq!(
         down_rightwards_game.render_to_string(&mut render_to),
         correct_down_rightwards
     );
 }
+
+#[test]
+fn test_drawing_wrapped_snakes() {
+    let (default_width, default_height): (usize, usize) = (3, 3);
+    let mut render_to = vec![vec![' '; default_width]; default_height];
+    let wrapped_game = |body: &[(i32, i32)]| {
+        let mut game = Game::new(default_width, default_height, body, 0);
+        game.snake.wraps = true;
+        game.snake.growth = 1;
+        game.snake.advance();
+        game.snake.advance();
+        game
+    };
+    #[rustfmt::skip]
+    let correct_down_wrapped = concat!(" v \n",
+                                             " ║ \n",
+                                             " ║ \n");
+    let mut down_wrapped_game = wrapped_game(&[(1, 1), (0, 1)]);
+    assert_eq!(down_wrapped_game.snake.body, vec![(0, 1), (2, 1), (1, 1)]);
+    assert_eq!(
+        down_wrapped_game.render_to_string(&mut render_to),
+        correct_down_wrapped
+    );
+    #[rustfmt::skip]
+    let correct_right_wrapped = concat!("   \n",
+                                              ">══\n",
+                                              "   \n");
+    let mut right_wrapped_game = wrapped_game(&[(1, 1), (1, 0)]);
+    assert_eq!(right_wrapped_game.snake.body, vec![(1, 0), (1, 2), (1, 1)]);
+    assert_eq!(
+        right_wrapped_game.render_to_string(&mut render_to),
+        correct_right_wrapped
+    );
+}
+
+#[test]
+fn test_drawing_a_snake_that_hit_a_wall() {
+    let mut render_to = vec![vec![' '; 3]; 3];
+    let mut game = Game::new(3, 3, &[(0, 1), (1, 1), (2, 1)], 0);
+    game.advance();
+    assert_eq!(game.death, Some(crate::DeathCause::Wall));
+    #[rustfmt::skip]
+    let correct = concat!(" ║ \n",
+                          " ║ \n",
+                          "   \n");
+    assert_eq!(game.render_to_string(&mut render_to), correct);
+}
+
+#[test]
+fn test_drawing_the_end_of_a_replay_that_hit_a_wall() {
+    let setup = crate::settings::Setup::new(level::TERMINAL, 0);
+    let mut replay = Replay::new(setup).unwrap();
+    let mut game = replay.start.clone();
+    while !game.over {
+        replay.advance(&mut game);
+    }
+    let mut finish = replay.finish();
+    assert_eq!(finish.death, Some(crate::DeathCause::Wall));
+    let mut render_to = vec![vec![' '; finish.width]; finish.height];
+    #[rustfmt::skip]
+    let correct = concat!("                    \n",
+                          "                    \n",
+                          "                    \n",
+                          "                    \n",
+                          "    O               \n",
+                          "                    \n",
+                          "                    \n",
+                          "                    \n",
+                          "                    \n",
+                          "         OO         \n",
+                          "                ════\n",
+                          "                    \n",
+                          "                    \n",
+                          "                    \n",
+                          "                    \n",
+                          "                    \n",
+                          "                    \n",
+                          "                    \n",
+                          "                    \n",
+                          "                    \n");
+    assert_eq!(finish.render_to_string(&mut render_to), correct);
+}
@@ -1,12 +1,11 @@
+use crate::apple::Apples;
 use crate::print_rendering::Printable;
-use crate::Apple;
-use std::collections::HashSet;
 
-impl Printable for HashSet<Apple> {
+impl Printable for Apples {
     fn print(&mut self, rendered: &mut Vec<Vec<char>>) {
-        for apple in self.iter() {
+        for apple in self.values() {
             let (y, x) = apple.location;
-            rendered[y as usize][x as usize] = 'O';
+            rendered[y as usize][x as usize] = apple.glyph();
         }
     }
 }
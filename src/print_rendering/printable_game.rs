@@ -15,6 +15,7 @@ impl Printable for Game {
 impl Game {
     fn render(&mut self, rendered: &mut Vec<Vec<char>>) {
         self.clear(rendered);
+        self.obstacles.print(rendered);
         self.apples.print(rendered);
         self.snake.print(rendered);
     }
@@ -0,0 +1,12 @@
+use crate::print_rendering::Printable;
+use crate::Obstacle;
+use std::collections::HashSet;
+
+impl Printable for HashSet<Obstacle> {
+    fn print(&mut self, rendered: &mut Vec<Vec<char>>) {
+        for obstacle in self.iter() {
+            let (y, x) = obstacle.location;
+            rendered[y as usize][x as usize] = '#';
+        }
+    }
+}
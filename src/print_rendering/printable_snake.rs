@@ -12,35 +12,48 @@ impl Printable for Snake {
 
 impl Snake {
     fn render_snake_head(&mut self, rendered: &mut Vec<Vec<char>>) {
-        let direction = Snake::head_direction(self.body.iter());
-        let (y, x) = self.body[0];
-        let glyph = match direction {
-            Direction::Up => '^',
-            Direction::Right => '>',
-            Direction::Down => 'v',
-            Direction::Left => '<',
-        };
-        rendered[y as usize][x as usize] = glyph;
+        Snake::render_glyph(
+            rendered,
+            self.body[0],
+            Snake::get_head_glyph(self.direction),
+        );
     }
 
     fn render_snake_body(&mut self, rendered: &mut Vec<Vec<char>>) {
         for window in Vec::from(self.body.clone()).windows(3) {
-            let to = Snake::direction(window[0], window[1]);
-            let from = Snake::direction(window[1], window[2]);
+            let to = Snake::direction(window[0], window[1], self.confines, self.wraps);
+            let from = Snake::direction(window[1], window[2], self.confines, self.wraps);
             let joining_glyph = Snake::get_body_glyph_from_directions(to, from);
-            let (y_to_set, x_to_set) = window[1];
-            rendered[y_to_set as usize][x_to_set as usize] = joining_glyph;
+            Snake::render_glyph(rendered, window[1], joining_glyph);
         }
     }
 
     fn render_snake_tail(&mut self, rendered: &mut Vec<Vec<char>>) {
         let relevant_points: Vec<_> = self.body.iter().rev().take(2).rev().cloned().collect();
-        let direction = Snake::direction(relevant_points[0], relevant_points[1]);
-        let (y, x) = self.body.back().unwrap();
+        let direction = Snake::direction(
+            relevant_points[0],
+            relevant_points[1],
+            self.confines,
+            self.wraps,
+        );
         let glyph = match direction {
             Direction::Up | Direction::Down => '║',
             Direction::Right | Direction::Left => '═',
         };
-        rendered[*y as usize][*x as usize] = glyph;
+        Snake::render_glyph(rendered, *self.body.back().unwrap(), glyph);
+    }
+
+    /// Draws `glyph` on `(y, x)`, unless it's off the board, as the head is
+    /// once the snake has run into a wall.
+    fn render_glyph(rendered: &mut Vec<Vec<char>>, (y, x): (i32, i32), glyph: char) {
+        if y < 0 || x < 0 {
+            return;
+        }
+        if let Some(cell) = rendered
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+        {
+            *cell = glyph;
+        }
     }
 }
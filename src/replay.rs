@@ -0,0 +1,372 @@
+//! Recording games as replays and playing them back.
+//!
+//! A replay is the `Setup` a game was started from, meaning the level, the
+//! seed and the options it was played with, plus the turn the snake took on
+//! each tick. The simulation is deterministic, so starting the game again
+//! from the setup and feeding those turns back through `Game::advance` plays
+//! out exactly the same game. Only games started from a setup can be
+//! recorded, so a replay file never gets to say what the board looked like.
+use crate::event::{DeathCause, Event};
+use crate::save::check_version;
+use crate::settings::Setup;
+use crate::snake::Direction;
+use crate::Game;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The format `Replay::save` writes, bumped whenever it changes.
+pub const REPLAY_VERSION: u32 = 2;
+
+/// The fastest and slowest a `Player` can play a replay.
+const SPEEDS: (f32, f32) = (0.25, 16.0);
+
+#[derive(Clone)]
+pub struct Replay {
+    /// What the recorded game was started from.
+    pub setup: Setup,
+    /// The game before the first recorded tick, as `setup` starts it.
+    pub start: Game,
+    /// One glyph per tick for the turn taken on it: `U`, `R`, `D` or `L`,
+    /// or `.` for carrying straight on.
+    pub inputs: String,
+    /// How the recorded game ended up, according to whoever recorded it.
+    pub claimed: Option<Outcome>,
+}
+
+/// Where a game ended up, for checking a replay plays out the way its
+/// recording claims.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Outcome {
+    pub score: u32,
+    pub length: usize,
+    pub ticks: u32,
+    pub death: Option<DeathCause>,
+    pub won: bool,
+    /// The game's `state_hash`.
+    pub hash: u64,
+}
+
+impl Outcome {
+    pub fn of(game: &Game) -> Outcome {
+        Outcome {
+            score: game.score,
+            length: game.snake.body.len(),
+            ticks: game.ticks,
+            death: game.death,
+            won: game.won,
+            hash: game.state_hash(),
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ending = match (self.death, self.won) {
+            (Some(cause), _) => format!("the snake {}", cause),
+            (None, true) => String::from("board cleared"),
+            (None, false) => String::from("still going"),
+        };
+        write!(
+            f,
+            "score {}, length {}, {} ticks, {}, hash {:016x}",
+            self.score, self.length, self.ticks, ending, self.hash
+        )
+    }
+}
+
+/// The file `Replay::save` writes, as read back in. Its `version` is checked
+/// by `check_version` first, and there's no starting game in it, since that's
+/// built again from `setup`.
+#[derive(Deserialize)]
+struct ReplayFile {
+    setup: Setup,
+    inputs: String,
+    #[serde(default)]
+    claimed: Option<Outcome>,
+}
+
+/// `ReplayFile` for writing, borrowing the replay rather than copying it.
+#[derive(Serialize)]
+struct ReplayFileRef<'a> {
+    version: u32,
+    setup: &'a Setup,
+    inputs: &'a str,
+    claimed: Option<Outcome>,
+}
+
+fn input_glyph(turn: Option<Direction>) -> char {
+    match turn {
+        Some(Direction::Up) => 'U',
+        Some(Direction::Right) => 'R',
+        Some(Direction::Down) => 'D',
+        Some(Direction::Left) => 'L',
+        None => '.',
+    }
+}
+
+pub(crate) fn input_from_glyph(glyph: char) -> Option<Option<Direction>> {
+    match glyph {
+        'U' => Some(Some(Direction::Up)),
+        'R' => Some(Some(Direction::Right)),
+        'D' => Some(Some(Direction::Down)),
+        'L' => Some(Some(Direction::Left)),
+        '.' => Some(None),
+        _ => None,
+    }
+}
+
+impl Replay {
+    /// Starts recording a game from `setup`, which `start` holds. Fails if
+    /// `setup` couldn't have come from any `Settings`.
+    pub fn new(setup: Setup) -> Result<Replay, String> {
+        Ok(Replay {
+            start: setup.start()?,
+            setup,
+            inputs: String::new(),
+            claimed: None,
+        })
+    }
+
+    /// How many ticks have been recorded.
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// How many recorded ticks `game` is into the replay.
+    pub fn tick_of(&self, game: &Game) -> usize {
+        game.ticks.saturating_sub(self.start.ticks) as usize
+    }
+
+    /// The turn taken on `tick`, if any.
+    pub fn turn(&self, tick: usize) -> Option<Direction> {
+        self.inputs
+            .as_bytes()
+            .get(tick)
+            .and_then(|glyph| input_from_glyph(*glyph as char))
+            .unwrap_or(None)
+    }
+
+    /// Advances `game` a tick and records the turn it took. Anything
+    /// recorded after the tick `game` is on, because it's been rewound, is
+    /// dropped first.
+    pub fn advance(&mut self, game: &mut Game) -> Vec<Event> {
+        let tick = self.tick_of(game);
+        self.inputs.truncate(tick);
+        let direction = game.snake.direction;
+        let events = game.advance();
+        if !events.is_empty() {
+            let turned = game.snake.direction != direction;
+            self.inputs.push(input_glyph(if turned {
+                Some(game.snake.direction)
+            } else {
+                None
+            }));
+        }
+        events
+    }
+
+    /// Plays the recorded tick `game` is on, ignoring any turns queued on
+    /// it. Does nothing once the recording runs out.
+    pub fn play_tick(&self, game: &mut Game) -> Vec<Event> {
+        let tick = self.tick_of(game);
+        if tick >= self.len() {
+            return vec![];
+        }
+        game.snake.turns.clear();
+        game.snake.turns.extend(self.turn(tick));
+        game.advance()
+    }
+
+    /// The game as it was after `tick` recorded ticks, or at the end of the
+    /// recording if it's shorter. A tampered recording can end the game
+    /// early, in which case playback stops there.
+    pub fn game_at(&self, tick: usize) -> Game {
+        let mut game = self.start.clone();
+        while !game.over && self.tick_of(&game) < tick.min(self.len()) {
+            self.play_tick(&mut game);
+        }
+        game
+    }
+
+    /// The game as it was at the end of the recording.
+    pub fn finish(&self) -> Game {
+        self.game_at(self.len())
+    }
+
+    /// Records how `game`, the game being recorded, has ended up so far.
+    pub fn claim(&mut self, game: &Game) {
+        self.claimed = Some(Outcome::of(game));
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&ReplayFileRef {
+            version: REPLAY_VERSION,
+            setup: &self.setup,
+            inputs: &self.inputs,
+            claimed: self.claimed,
+        })
+        .unwrap()
+    }
+
+    pub fn from_json(text: &str) -> Result<Replay, String> {
+        check_version(text, REPLAY_VERSION)?;
+        let file: ReplayFile = serde_json::from_str(text).map_err(|error| error.to_string())?;
+        if let Some(glyph) = file
+            .inputs
+            .chars()
+            .find(|glyph| input_from_glyph(*glyph).is_none())
+        {
+            return Err(format!("unexpected '{}' in the inputs", glyph));
+        }
+        Ok(Replay {
+            inputs: file.inputs,
+            claimed: file.claimed,
+            ..Replay::new(file.setup)?
+        })
+    }
+
+    /// Writes the replay to `path`, replacing anything already there.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_json())
+            .map_err(|error| format!("Couldn't write {}: {}", path.display(), error))
+    }
+
+    /// Reads a replay written with `save` back from `path`.
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+        Replay::from_json(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+}
+
+/// How a replay is being watched: the recording, and how many times faster
+/// than it was played it's being shown.
+pub struct Player {
+    pub replay: Replay,
+    pub speed: f32,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Player {
+        Player { replay, speed: 1.0 }
+    }
+
+    /// Whether `game` has reached the end of the recording.
+    pub fn finished(&self, game: &Game) -> bool {
+        game.over || self.replay.tick_of(game) >= self.replay.len()
+    }
+
+    /// Moves `game` to `tick`, playing forwards from where it is or starting
+    /// again from the beginning to go backwards.
+    pub fn seek(&self, game: &mut Game, tick: usize) {
+        if tick < self.replay.tick_of(game) {
+            *game = self.replay.start.clone();
+        }
+        while !game.over && self.replay.tick_of(game) < tick.min(self.replay.len()) {
+            self.replay.play_tick(game);
+        }
+    }
+
+    /// Multiplies the playback speed by `factor`, within sensible limits.
+    pub fn change_speed(&mut self, factor: f32) {
+        self.speed = (self.speed * factor).max(SPEEDS.0).min(SPEEDS.1);
+    }
+
+    /// How long to show each tick of `game` for at the current speed.
+    pub fn seconds_between_ticks(&self, game: &Game) -> f32 {
+        game.seconds_between_ticks() / self.speed
+    }
+}
+
+#[test]
+fn test_replays_play_back_identically() {
+    let setup = Setup {
+        special_apples: true,
+        ..Setup::new(crate::level::TERMINAL, 99)
+    };
+    let mut replay = Replay::new(setup).unwrap();
+    let mut game = replay.start.clone();
+    for moves in ["", "w", "", "a", "as", "", "d", "", "", "wd"].iter() {
+        for direction in moves.chars().map(|glyph| match glyph {
+            'w' => Direction::Up,
+            'a' => Direction::Left,
+            's' => Direction::Down,
+            _ => Direction::Right,
+        }) {
+            game.snake.turn(direction);
+        }
+        replay.advance(&mut game);
+    }
+    assert_eq!(replay.len(), game.ticks as usize);
+    assert_eq!(&replay.inputs[..4], ".U.L");
+
+    let replay = Replay::from_json(&replay.to_json()).unwrap();
+    let played = replay.finish();
+    assert_eq!(played.snake.body, game.snake.body);
+    assert_eq!(played.apples, game.apples);
+    assert_eq!(played.score, game.score);
+
+    let player = Player::new(replay);
+    let mut watched = player.replay.start.clone();
+    player.seek(&mut watched, 6);
+    assert_eq!(watched.ticks, 6);
+    player.seek(&mut watched, 2);
+    assert_eq!(watched.snake.body, player.replay.game_at(2).snake.body);
+    assert!(!player.finished(&watched));
+}
+
+#[test]
+fn test_rewound_ticks_are_rerecorded() {
+    let mut replay = Replay::new(Setup::new(crate::level::TERMINAL, 0)).unwrap();
+    let mut game = replay.start.clone();
+    replay.advance(&mut game);
+    let rewind_to = game.clone();
+    game.snake.turn(Direction::Up);
+    replay.advance(&mut game);
+    game = rewind_to;
+    game.snake.turn(Direction::Down);
+    replay.advance(&mut game);
+    assert_eq!(replay.inputs, ".D");
+    assert!(Replay::from_json(&replay.to_json().replace(".D", ".x")).is_err());
+}
+
+#[test]
+fn test_checking_claims() {
+    let mut replay = Replay::new(Setup::new(crate::level::TERMINAL, 12)).unwrap();
+    let mut game = replay.start.clone();
+    while !game.over {
+        if game.ticks == 2 {
+            game.snake.turn(Direction::Up);
+        }
+        replay.advance(&mut game);
+    }
+    replay.claim(&game);
+    let claimed = replay.claimed.unwrap();
+    assert_eq!(claimed.death, Some(DeathCause::Wall));
+    assert_eq!(Outcome::of(&replay.finish()), claimed);
+
+    replay.inputs.replace_range(2..3, ".");
+    assert_ne!(Outcome::of(&replay.finish()), claimed);
+}
+
+#[test]
+fn test_replays_start_from_their_setup() {
+    let replay = Replay::new(Setup::new(crate::level::TERMINAL, 5)).unwrap();
+    let json = replay.to_json();
+    assert!(!json.contains("\"start\""));
+    let loaded = Replay::from_json(&json).unwrap();
+    assert_eq!(loaded.start.state_hash(), replay.start.state_hash());
+
+    let tampered = json.replacen("\"start_seconds\":0.2", "\"start_seconds\":-1.0", 1);
+    assert!(Replay::from_json(&tampered).is_err());
+    let tampered = json.replacen("\"seed\":5", "\"seed\":6", 1);
+    let loaded = Replay::from_json(&tampered).unwrap();
+    assert_eq!(loaded.start.seed, 6);
+    assert!(Replay::new(Setup::new("-----\n|   |\n-----", 0)).is_err());
+}
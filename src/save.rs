@@ -0,0 +1,241 @@
+//! Saving a game in progress to disk and picking it up again later.
+//!
+//! A save file is JSON holding the format `version` and the whole `Game`:
+//! the snake with its direction, queued turns and pending growth, the apples
+//! and obstacles, the score and board size, the apple placement RNG's state,
+//! and the options the game was started with. The occupancy grid is worked
+//! out again on loading, once everything on the board has been checked to be
+//! on it and could have got there in a real game. Loading a save carries on
+//! exactly where it left off, apple placement included.
+use crate::event::DeathCause;
+use crate::snake::{Direction, Snake};
+use crate::Game;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The format `save` writes, bumped whenever it changes.
+pub const SAVE_VERSION: u32 = 1;
+
+/// The file `save` writes, as read back in. Its `version` is checked by
+/// `check_version` first.
+#[derive(Deserialize)]
+struct SaveFile {
+    game: Game,
+}
+
+/// `SaveFile` for writing, borrowing the game rather than copying it.
+#[derive(Serialize)]
+struct SaveFileRef<'a> {
+    version: u32,
+    game: &'a Game,
+}
+
+/// The version of a file, read on its own so a file from another version
+/// gets a clear error rather than a confusing one.
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+/// Checks the JSON in `text` is a version `expected` file.
+pub(crate) fn check_version(text: &str, expected: u32) -> Result<(), String> {
+    let version: Version = serde_json::from_str(text).map_err(|error| error.to_string())?;
+    if version.version != expected {
+        return Err(format!(
+            "this is a version {} file, but only version {} can be loaded",
+            version.version, expected
+        ));
+    }
+    Ok(())
+}
+
+impl Game {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&SaveFileRef {
+            version: SAVE_VERSION,
+            game: self,
+        })
+        .unwrap()
+    }
+
+    pub fn from_json(text: &str) -> Result<Game, String> {
+        check_version(text, SAVE_VERSION)?;
+        let mut save: SaveFile = serde_json::from_str(text).map_err(|error| error.to_string())?;
+        save.game.restore()?;
+        Ok(save.game)
+    }
+
+    /// Gets a game that's just been deserialized ready to play, then works
+    /// out the occupancy grid. Everything is checked first: the board isn't
+    /// empty, the difficulty is a preset, the snake is a head and at least
+    /// one segment joined up cell by cell, and the snake, apples and
+    /// obstacles are all on the board without overlapping. The head of a
+    /// snake that died is allowed off the board or on what it ran into.
+    fn restore(&mut self) -> Result<(), String> {
+        let on_board = |(y, x): (i32, i32)| {
+            y >= 0 && x >= 0 && (y as usize) < self.height && (x as usize) < self.width
+        };
+        if self.width == 0 || self.height == 0 {
+            return Err(String::from("the board is empty"));
+        }
+        if self.snake.confines != (self.height as i32, self.width as i32) {
+            return Err(String::from("the snake's confines don't match the board"));
+        }
+        if !self.difficulty.is_preset() {
+            return Err(String::from("the difficulty isn't easy, normal or hard"));
+        }
+        if self.snake.body.len() < 2 {
+            return Err(String::from(
+                "the snake is shorter than a head and one segment",
+            ));
+        }
+        let crashed = self.death == Some(DeathCause::Wall);
+        for (index, location) in self.snake.body.iter().enumerate() {
+            if !(on_board(*location) || index == 0 && crashed) {
+                return Err(format!("the snake is off the board at {:?}", location));
+            }
+        }
+        let snake = &self.snake;
+        let joined = |now: (i32, i32), (y, x): (i32, i32)| {
+            Direction::ALL.iter().any(|direction| {
+                let (dy, dx) = Snake::advancement_to_add(direction);
+                let next = (y + dy, x + dx);
+                next == now || snake.board_location(next) == Some(now)
+            })
+        };
+        for (now, then) in snake.body.iter().zip(snake.body.iter().skip(1)) {
+            if !joined(*now, *then) {
+                return Err(format!(
+                    "the snake's segments at {:?} and {:?} aren't next to each other",
+                    now, then
+                ));
+            }
+        }
+        for location in self.apples.keys() {
+            if !on_board(*location) {
+                return Err(format!("an apple is off the board at {:?}", location));
+            }
+            if self.snake.body.contains(location) {
+                return Err(format!("an apple is on the snake at {:?}", location));
+            }
+        }
+        let hit_obstacle = self.death == Some(DeathCause::Obstacle);
+        for obstacle in self.obstacles.iter() {
+            let location = obstacle.location;
+            if !on_board(location) {
+                return Err(format!("an obstacle is off the board at {:?}", location));
+            }
+            let under_head = hit_obstacle && self.snake.body[0] == location;
+            if self.snake.body.contains(&location) && !under_head {
+                return Err(format!("an obstacle is on the snake at {:?}", location));
+            }
+            if self.apples.contains_key(&location) {
+                return Err(format!("an apple is on an obstacle at {:?}", location));
+            }
+        }
+        self.rebuild_occupancy();
+        Ok(())
+    }
+
+    /// Writes the game to `path`, replacing anything already there.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_json())
+            .map_err(|error| format!("Couldn't write {}: {}", path.display(), error))
+    }
+
+    /// Reads a game saved with `save` back from `path`.
+    pub fn load(path: &Path) -> Result<Game, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+        Game::from_json(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+}
+
+#[test]
+fn test_saved_games_carry_on_identically() {
+    let mut game = crate::level::Level::parse(crate::level::TERMINAL)
+        .unwrap()
+        .start(7);
+    game.special_apples = true;
+    game.apple_lifetime = Some(5);
+    game.snake.turn(crate::Direction::Up);
+    game.advance();
+    game.snake.turn(crate::Direction::Left);
+    let mut loaded = Game::from_json(&game.to_json()).unwrap();
+    assert_eq!(loaded.snake.body, game.snake.body);
+    assert_eq!(loaded.apple_lifetime, Some(5));
+    for _ in 0..8 {
+        assert_eq!(loaded.advance(), game.advance());
+    }
+    assert_eq!(loaded.apples, game.apples);
+    assert_eq!(loaded.score, game.score);
+}
+
+#[test]
+fn test_loading_other_versions() {
+    let json = Game::new(3, 3, &[(0, 1), (0, 0)], 0).to_json().replacen(
+        "\"version\":1",
+        "\"version\":99",
+        1,
+    );
+    assert!(Game::from_json(&json).err().unwrap().contains("version 99"));
+    assert!(Game::from_json("{}").is_err());
+}
+
+#[test]
+fn test_loading_things_off_the_board() {
+    let game = Game::new(3, 3, &[(0, 1), (0, 0)], 0);
+    let json = game.to_json();
+    let moved = |from: &str, to: &str| Game::from_json(&json.replacen(from, to, 1));
+    assert!(moved("[[0,1],[0,0]]", "[[0,1],[0,3]]")
+        .err()
+        .unwrap()
+        .contains("snake is off the board"));
+    let mut with_apple = game.clone();
+    with_apple.add_apple(crate::Apple::new((2, 2)));
+    let json = with_apple.to_json();
+    assert!(Game::from_json(&json.replacen("[2,2]", "[2,-1]", 1))
+        .err()
+        .unwrap()
+        .contains("apple is off the board"));
+    let loaded = Game::from_json(&json).unwrap();
+    assert_eq!(loaded.occupancy().empty_cells(), 9 - 3);
+
+    let mut crashed = game;
+    crashed.snake.turn(crate::Direction::Up);
+    crashed.advance();
+    assert!(Game::from_json(&crashed.to_json()).is_ok());
+}
+
+#[test]
+fn test_loading_impossible_games() {
+    let error = |game: &Game, from: &str, to: &str| {
+        Game::from_json(&game.to_json().replacen(from, to, 1))
+            .err()
+            .unwrap()
+    };
+    let mut game = Game::new(3, 3, &[(0, 1), (0, 0)], 0);
+    assert!(error(&game, "[[0,1],[0,0]]", "[[0,1]]").contains("shorter"));
+    assert!(error(&game, "[[0,1],[0,0]]", "[[0,2],[0,0]]").contains("next to each other"));
+    assert!(error(&game, "\"max_level\":10", "\"max_level\":11").contains("difficulty"));
+    game.snake.wraps = true;
+    let wrapped = game.to_json().replacen("[[0,1],[0,0]]", "[[0,2],[0,0]]", 1);
+    assert!(Game::from_json(&wrapped).is_ok());
+
+    let mut game = Game::new(3, 3, &[(0, 1), (0, 0)], 0);
+    game.add_apple(crate::Apple::new((2, 2)));
+    game.add_obstacle((1, 1));
+    assert!(error(&game, "[2,2]", "[0,0]").contains("apple is on the snake"));
+    assert!(error(&game, "[1,1]", "[0,1]").contains("obstacle is on the snake"));
+    assert!(error(&game, "[1,1]", "[2,2]").contains("apple is on an obstacle"));
+    game.add_apple(crate::Apple::new((1, 2)));
+    assert!(error(&game, "[2,2]", "[1,2]").contains("two apples"));
+
+    let mut crashed = Game::new(3, 3, &[(0, 2), (0, 1), (0, 0)], 0);
+    crashed.add_obstacle((1, 2));
+    crashed.snake.turn(crate::Direction::Down);
+    crashed.advance();
+    assert_eq!(crashed.death, Some(DeathCause::Obstacle));
+    assert!(Game::from_json(&crashed.to_json()).is_ok());
+}
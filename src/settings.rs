@@ -0,0 +1,212 @@
+use crate::difficulty::Difficulty;
+use crate::level::Level;
+use crate::Game;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Options shared by both front-ends, read from the command line.
+pub struct Settings {
+    /// The seed for the apple placement RNG, random unless `--seed` is given.
+    pub seed: u64,
+    /// Set by `--wrap`: the snake leaves one edge and comes back in on the
+    /// opposite one instead of dying.
+    pub wrapping: bool,
+    /// A level file to start from, given with `--level`, like
+    /// `--level levels/arena.txt`.
+    pub level: Option<PathBuf>,
+    /// Set by `--special-apples`: new apples can be golden, feast,
+    /// shrinking, speed or poisoned ones.
+    pub special_apples: bool,
+    /// How many ticks apples last, given with `--apple-lifetime`.
+    pub apple_lifetime: Option<u32>,
+    /// How many apples to keep on the board, given with `--apples`. Without
+    /// it, the level's own apple count is kept.
+    pub apple_target: Option<usize>,
+    /// How fast the game speeds up, picked with `--difficulty`.
+    pub difficulty: Difficulty,
+    /// A saved game to carry on with, given with `--load`.
+    pub load: Option<PathBuf>,
+    /// Where to write a replay of the game once it's over, given with
+    /// `--record`.
+    pub record: Option<PathBuf>,
+    /// A replay to watch instead of playing, given with `--replay`.
+    pub replay: Option<PathBuf>,
+    /// Set by `--autopilot`: the snake steers itself, until the ggez
+    /// front-end has it switched off.
+    pub autopilot: bool,
+}
+
+impl Settings {
+    /// Reads settings from `args`, ignoring anything it doesn't recognise.
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Settings, String> {
+        let mut seed = None;
+        let mut wrapping = false;
+        let mut level = None;
+        let mut special_apples = false;
+        let mut apple_lifetime = None;
+        let mut apple_target = None;
+        let mut difficulty = Difficulty::default();
+        let mut load = None;
+        let mut record = None;
+        let mut replay = None;
+        let mut autopilot = false;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(value)) => seed = Some(value),
+                    _ => return Err(String::from("--seed expects a non-negative integer")),
+                },
+                "--wrap" => wrapping = true,
+                "--level" => match args.next() {
+                    Some(path) => level = Some(PathBuf::from(path)),
+                    None => return Err(String::from("--level expects a file")),
+                },
+                "--special-apples" => special_apples = true,
+                "--autopilot" => autopilot = true,
+                "--apples" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(value)) if value > 0 => apple_target = Some(value),
+                    _ => return Err(String::from("--apples expects a positive integer")),
+                },
+                "--difficulty" => match args.next().and_then(|name| Difficulty::from_name(&name)) {
+                    Some(value) => difficulty = value,
+                    None => return Err(String::from("--difficulty expects easy, normal or hard")),
+                },
+                "--load" => match args.next() {
+                    Some(path) => load = Some(PathBuf::from(path)),
+                    None => return Err(String::from("--load expects a file")),
+                },
+                "--record" => match args.next() {
+                    Some(path) => record = Some(PathBuf::from(path)),
+                    None => return Err(String::from("--record expects a file")),
+                },
+                "--replay" => match args.next() {
+                    Some(path) => replay = Some(PathBuf::from(path)),
+                    None => return Err(String::from("--replay expects a file")),
+                },
+                "--apple-lifetime" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(value)) if value > 0 => apple_lifetime = Some(value),
+                    _ => return Err(String::from("--apple-lifetime expects a positive integer")),
+                },
+                _ => (),
+            }
+        }
+        Ok(Settings {
+            seed: seed.unwrap_or_else(rand::random),
+            wrapping,
+            level,
+            special_apples,
+            apple_lifetime,
+            apple_target,
+            difficulty,
+            load,
+            record,
+            replay,
+            autopilot,
+        })
+    }
+
+    /// The level given with `--level`, or `default` if there wasn't one.
+    pub fn level(&self, default: &str) -> Result<Level, String> {
+        match &self.level {
+            Some(path) => Level::load(path),
+            None => Level::parse(default).map_err(|error| error.to_string()),
+        }
+    }
+
+    /// Starts a game on `level` with these settings, with the board already
+    /// topped up with apples.
+    pub fn start(&self, level: &Level) -> Game {
+        let mut game = self.prepare(level);
+        game.top_up_apples();
+        game
+    }
+
+    /// A game on `level` with these settings, before any apples besides the
+    /// level's own have been placed.
+    pub fn prepare(&self, level: &Level) -> Game {
+        self.setup(level).prepare(level)
+    }
+
+    /// What a new game on `level` with these settings is set up from.
+    pub fn setup(&self, level: &Level) -> Setup {
+        Setup {
+            level: level.text.clone(),
+            seed: self.seed,
+            wrapping: self.wrapping,
+            special_apples: self.special_apples,
+            apple_lifetime: self.apple_lifetime,
+            apple_target: self.apple_target,
+            difficulty: self.difficulty,
+        }
+    }
+
+    /// The saved game given with `--load`, or otherwise a new game on
+    /// `level`.
+    pub fn starting_game(&self, level: &Level) -> Result<Game, String> {
+        match &self.load {
+            Some(path) => Game::load(path),
+            None => Ok(self.start(level)),
+        }
+    }
+}
+
+/// Everything a new game is set up from: the level, the seed and the
+/// settings that change how it plays. Replays keep this rather than the game
+/// itself, so a replay always starts from a game built afresh.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Setup {
+    /// The level, as it's written in a level file.
+    pub level: String,
+    pub seed: u64,
+    pub wrapping: bool,
+    pub special_apples: bool,
+    pub apple_lifetime: Option<u32>,
+    pub apple_target: Option<usize>,
+    pub difficulty: Difficulty,
+}
+
+impl Setup {
+    /// Sets up a game on the level written as `level`, seeded `seed`, with
+    /// every other setting left at its default.
+    pub fn new(level: &str, seed: u64) -> Setup {
+        Setup {
+            level: String::from(level),
+            seed,
+            wrapping: false,
+            special_apples: false,
+            apple_lifetime: None,
+            apple_target: None,
+            difficulty: Difficulty::default(),
+        }
+    }
+
+    /// A game on `level`, which should be the one parsed from `self.level`,
+    /// before any apples besides the level's own have been placed.
+    pub fn prepare(&self, level: &Level) -> Game {
+        let mut game = level.start(self.seed);
+        game.snake.wraps = self.wrapping;
+        game.special_apples = self.special_apples;
+        game.apple_lifetime = self.apple_lifetime;
+        game.difficulty = self.difficulty;
+        if let Some(target) = self.apple_target {
+            game.apple_target = target;
+        }
+        game
+    }
+
+    /// Starts the game this sets up, with the board already topped up with
+    /// apples, or explains why it can't have come from any `Settings`.
+    pub fn start(&self) -> Result<Game, String> {
+        if !self.difficulty.is_preset() {
+            return Err(String::from("the difficulty isn't easy, normal or hard"));
+        }
+        if self.apple_lifetime == Some(0) || self.apple_target == Some(0) {
+            return Err(String::from("apple lifetimes and targets must be positive"));
+        }
+        let level = Level::parse(&self.level)
+            .map_err(|error| format!("the level doesn't parse: {}", error))?;
+        let mut game = self.prepare(&level);
+        game.top_up_apples();
+        Ok(game)
+    }
+}
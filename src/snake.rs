@@ -1,6 +1,12 @@
+use crate::event::DeathCause;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// How many turns can be waiting to be applied, one per `advance`.
+pub const MAX_QUEUED_TURNS: usize = 3;
+
+/// The way the snake's head is travelling across the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Right,
@@ -8,19 +14,74 @@ pub enum Direction {
     Left,
 }
 
+impl Direction {
+    /// Every direction, clockwise from `Up`.
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// The direction pointing the other way, which the snake can't turn to.
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    /// The direction a quarter turn to the right of this one.
+    pub fn clockwise(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// The direction a quarter turn to the left of this one.
+    pub fn anticlockwise(self) -> Direction {
+        self.clockwise().opposite()
+    }
+}
+
+/// A snake whose segments are stored head first as `(y, x)` cells.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Snake {
+    /// The direction the head moved in on the last `advance`.
     pub direction: Direction,
-    pub lengthening: bool,
+    /// Turns requested with `turn` that haven't been applied yet.
+    pub(crate) turns: VecDeque<Direction>,
+    /// How many more `advance`s keep the tail, growing the snake by one
+    /// segment each.
+    pub growth: u32,
     pub body: VecDeque<(i32, i32)>,
+    /// The board size as `(height, width)`; leaving it kills the snake
+    /// unless it `wraps`.
     pub confines: (i32, i32),
+    /// Whether leaving one edge of `confines` brings the head back in on the
+    /// opposite edge.
+    pub wraps: bool,
+    /// The size in pixels the board is drawn at by the ggez front-end,
+    /// which fits it to its window rather than saving it.
+    #[serde(skip, default = "default_confines_size")]
     pub confines_size: (f32, f32),
 }
 
+fn default_confines_size() -> (f32, f32) {
+    (550.0, 550.0)
+}
+
 impl Snake {
+    /// How many pixels wide and high each cell is drawn.
     pub fn intervals(&self) -> (f32, f32) {
         (
-            self.confines_size.0 / self.confines.0 as f32,
-            self.confines_size.1 / self.confines.1 as f32,
+            self.confines_size.0 / self.confines.1 as f32,
+            self.confines_size.1 / self.confines.0 as f32,
         )
     }
 
@@ -28,32 +89,95 @@ impl Snake {
     pub fn from_body(body: &[(i32, i32)]) -> Self {
         Snake {
             body: VecDeque::from(Vec::from(body)),
-            direction: Snake::head_direction(body.iter()),
+            direction: Snake::head_direction(body.iter(), (20, 20), false),
+            turns: VecDeque::new(),
             confines: (20, 20),
-            lengthening: false,
+            wraps: false,
+            growth: 0,
             confines_size: (550.0, 550.0),
         }
     }
 
+    /// Queues a turn to be applied on a later `advance`, one turn per tick.
+    ///
+    /// The turn is checked against the direction that will be in effect when
+    /// it's applied, so two quick presses can't reverse the snake. Returns
+    /// whether the turn was queued; turns that don't change direction, turns
+    /// back on itself and turns past `MAX_QUEUED_TURNS` are dropped.
+    pub fn turn(&mut self, direction: Direction) -> bool {
+        let previous = *self.turns.back().unwrap_or(&self.direction);
+        if direction == previous
+            || direction == previous.opposite()
+            || self.turns.len() >= MAX_QUEUED_TURNS
+        {
+            return false;
+        }
+        self.turns.push_back(direction);
+        true
+    }
+
+    /// Applies the next queued turn, then moves the head one cell in
+    /// `direction`, dropping the tail unless the snake is growing.
     pub fn advance(&mut self) {
+        if let Some(direction) = self.turns.pop_front() {
+            self.direction = direction;
+        }
         let (dy, dx) = Snake::advancement_to_add(&self.direction);
         let (y, x) = self.body.front().unwrap();
         let new = ((*y + dy), (*x + dx));
-        self.body.push_front(new);
-        if self.lengthening {
-            self.lengthening = false;
+        self.body
+            .push_front(self.board_location(new).unwrap_or(new));
+        if self.growth > 0 {
+            self.growth -= 1;
         } else {
             self.body.pop_back();
         }
     }
 
+    /// Takes up to `segments` off the tail, never leaving the snake shorter
+    /// than a head and one segment. Returns the cells it gave up.
+    pub fn shrink(&mut self, segments: usize) -> Vec<(i32, i32)> {
+        let segments = segments.min(self.body.len().saturating_sub(2));
+        (0..segments).filter_map(|_| self.body.pop_back()).collect()
+    }
+
+    /// Whether the head has left the board or run into the rest of the body.
     pub fn dead(&self) -> bool {
+        self.death_cause().is_some()
+    }
+
+    /// What the head has run into, if anything. The snake doesn't know about
+    /// obstacles, so this is only ever `Wall` or `OwnBody`.
+    pub fn death_cause(&self) -> Option<DeathCause> {
         let (y, x) = self.body.front().unwrap();
         let (y, x) = (*y as i32, *x as i32);
-        self.dead_at((y, x))
+        self.death_cause_at((y, x))
     }
 
-    fn advancement_to_add(direction: &Direction) -> (i32, i32) {
+    /// Where `location` is on the board, wrapped round if the snake wraps,
+    /// or `None` if it's off the board.
+    pub fn board_location(&self, (y, x): (i32, i32)) -> Option<(i32, i32)> {
+        let (height, width) = self.confines;
+        let (y, x) = if self.wraps {
+            (y.rem_euclid(height), x.rem_euclid(width))
+        } else {
+            (y, x)
+        };
+        if y >= 0 && x >= 0 && y < height && x < width {
+            Some((y, x))
+        } else {
+            None
+        }
+    }
+
+    /// Where a step in `direction` from `location` ends up, or `None` if it
+    /// leaves the board.
+    pub fn step(&self, (y, x): (i32, i32), direction: Direction) -> Option<(i32, i32)> {
+        let (dy, dx) = Snake::advancement_to_add(&direction);
+        self.board_location((y + dy, x + dx))
+    }
+
+    pub(crate) fn advancement_to_add(direction: &Direction) -> (i32, i32) {
         match direction {
             Direction::Up => (-1, 0),
             Direction::Right => (0, 1),
@@ -62,16 +186,29 @@ impl Snake {
         }
     }
 
-    fn dead_at(&self, (y, x): (i32, i32)) -> bool {
-        y < 0
-            || x < 0
-            || y >= self.confines.0 as i32
-            || x >= self.confines.1 as i32
-            || self
-                .body
-                .iter()
-                .skip(1)
-                .any(|pos| (pos.0 as i32, pos.1 as i32) == (y, x))
+    fn death_cause_at(&self, (y, x): (i32, i32)) -> Option<DeathCause> {
+        let outside = y < 0 || x < 0 || y >= self.confines.0 as i32 || x >= self.confines.1 as i32;
+        if outside && !self.wraps {
+            Some(DeathCause::Wall)
+        } else if self
+            .body
+            .iter()
+            .skip(1)
+            .any(|pos| (pos.0 as i32, pos.1 as i32) == (y, x))
+        {
+            Some(DeathCause::OwnBody)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_head_glyph(direction: Direction) -> char {
+        match direction {
+            Direction::Up => '^',
+            Direction::Right => '>',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+        }
     }
 
     pub fn get_body_glyph_from_directions(to: Direction, from: Direction) -> char {
@@ -95,18 +232,89 @@ impl Snake {
         }
     }
 
-    pub fn head_direction<'a, T: Iterator<Item = &'a (i32, i32)>>(body_iter: T) -> Direction {
+    pub fn head_direction<'a, T: Iterator<Item = &'a (i32, i32)>>(
+        body_iter: T,
+        confines: (i32, i32),
+        wraps: bool,
+    ) -> Direction {
         let start_copy: Vec<_> = body_iter.take(2).collect();
-        Snake::direction(*start_copy[0], *start_copy[1])
+        Snake::direction(*start_copy[0], *start_copy[1], confines, wraps)
     }
 
-    pub fn direction((now_y, now_x): (i32, i32), (then_y, then_x): (i32, i32)) -> Direction {
-        match (now_y - then_y, now_x - then_x) {
-            (-1, 0) => Direction::Up,
-            (1, 0) => Direction::Down,
-            (0, 1) => Direction::Right,
-            (0, -1) => Direction::Left,
-            x => panic!("Invalid direction determined: {:?}", x),
-        }
+    /// The direction of travel from the cell `then` to the neighbouring cell
+    /// `now`, on a board of `confines` as `(height, width)`. Cells on
+    /// opposite edges are only neighbours if the board `wraps`, and a step
+    /// that doesn't cross an edge is preferred where both would fit.
+    ///
+    /// Panics if the cells aren't neighbours.
+    pub fn direction(
+        now: (i32, i32),
+        (then_y, then_x): (i32, i32),
+        (height, width): (i32, i32),
+        wraps: bool,
+    ) -> Direction {
+        let stepped = |direction: &Direction| {
+            let (dy, dx) = Snake::advancement_to_add(direction);
+            (then_y + dy, then_x + dx)
+        };
+        let wrapped = |direction: &Direction| {
+            let (y, x) = stepped(direction);
+            (y.rem_euclid(height), x.rem_euclid(width))
+        };
+        Direction::ALL
+            .iter()
+            .find(|direction| stepped(direction) == now)
+            .or_else(|| {
+                Direction::ALL
+                    .iter()
+                    .find(|direction| wraps && wrapped(direction) == now)
+            })
+            .cloned()
+            .unwrap_or_else(|| panic!("{:?} isn't a step from {:?}", now, (then_y, then_x)))
     }
 }
+
+#[test]
+fn test_wrapping_advance() {
+    let mut snake = Snake::from_body(&[(0, 5), (1, 5)]);
+    snake.wraps = true;
+    snake.advance();
+    assert_eq!(snake.body, vec![(19, 5), (0, 5)]);
+    assert!(!snake.dead());
+    assert_eq!(
+        Snake::head_direction(snake.body.iter(), snake.confines, true),
+        Direction::Up
+    );
+
+    snake.wraps = false;
+    snake.body = VecDeque::from(vec![(0, 5), (1, 5)]);
+    snake.advance();
+    assert!(snake.dead());
+}
+
+#[test]
+fn test_queued_turns() {
+    let mut snake = Snake::from_body(&[(10, 10), (10, 9)]);
+    assert!(!snake.turn(Direction::Left));
+    assert!(snake.turn(Direction::Down));
+    assert!(!snake.turn(Direction::Up));
+    assert!(snake.turn(Direction::Left));
+    assert!(snake.turn(Direction::Up));
+    assert!(!snake.turn(Direction::Right));
+    snake.advance();
+    assert_eq!(snake.body[0], (11, 10));
+    snake.advance();
+    assert_eq!(snake.body[0], (11, 9));
+    snake.advance();
+    assert_eq!(snake.body[0], (10, 9));
+    assert_eq!(snake.direction, Direction::Up);
+}
+
+#[test]
+fn test_intervals_on_a_wide_board() {
+    let mut snake = Snake::from_body(&[(0, 1), (0, 0)]);
+    snake.confines = (2, 5);
+    snake.confines_size = (500.0, 100.0);
+    let (x_interval, y_interval) = snake.intervals();
+    assert_eq!((x_interval as u32, y_interval as u32), (100, 50));
+}
@@ -0,0 +1,164 @@
+//! Measuring agents by playing them over many headless games.
+//!
+//! Every agent plays the same seeds, so their results can be compared
+//! directly.
+use crate::agent::Agent;
+use crate::environment::Environment;
+use crate::Game;
+use serde::Serialize;
+use std::fmt;
+use std::time::Instant;
+
+/// How many ticks per cell of the board an agent can go without eating
+/// before its game is stopped, so an agent going round in circles can't
+/// hold the tournament up forever.
+const PATIENCE_PER_CELL: u32 = 2;
+
+/// How an agent did over a set of games.
+#[derive(Clone, Debug, Serialize)]
+pub struct Results {
+    pub agent: String,
+    pub games: usize,
+    pub mean_score: f64,
+    pub median_score: f64,
+    pub max_score: u32,
+    /// How long the snake lasted on average, in ticks.
+    pub mean_ticks: f64,
+    pub median_ticks: f64,
+    /// The fraction of games where the snake filled the board.
+    pub clear_rate: f64,
+    /// How fast the games were simulated, agent included.
+    pub ticks_per_second: f64,
+}
+
+impl Results {
+    /// The header line for a table of results, lined up with `Display`.
+    pub fn header() -> String {
+        format!(
+            "{:<20} {:>6} {:>10} {:>8} {:>6} {:>10} {:>10} {:>8} {:>12}",
+            "Agent",
+            "Games",
+            "Mean score",
+            "Median",
+            "Max",
+            "Mean ticks",
+            "Median",
+            "Cleared",
+            "Ticks/s"
+        )
+    }
+}
+
+impl fmt::Display for Results {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<20} {:>6} {:>10.2} {:>8.1} {:>6} {:>10.1} {:>10.1} {:>7.1}% {:>12.0}",
+            self.agent,
+            self.games,
+            self.mean_score,
+            self.median_score,
+            self.max_score,
+            self.mean_ticks,
+            self.median_ticks,
+            self.clear_rate * 100.0,
+            self.ticks_per_second
+        )
+    }
+}
+
+fn mean(values: &[u32]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().map(|value| *value as f64).sum::<f64>() / values.len() as f64
+}
+
+fn median(values: &[u32]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    match sorted.len() {
+        0 => 0.0,
+        length if length % 2 == 0 => {
+            (sorted[length / 2 - 1] as f64 + sorted[length / 2] as f64) / 2.0
+        }
+        length => sorted[length / 2] as f64,
+    }
+}
+
+/// Plays `agent` over `games` games from `start`, seeded `seed`,
+/// `seed + 1` and so on. `start` should be as `Settings::prepare` leaves it,
+/// so each seed places its own apples.
+pub fn run(name: &str, agent: &mut dyn Agent, start: &Game, games: usize, seed: u64) -> Results {
+    let mut environment = Environment::new(start.clone());
+    environment.observe = |_| vec![];
+    environment.patience = Some(PATIENCE_PER_CELL * (start.width * start.height) as u32);
+    let mut scores = vec![];
+    let mut ticks = vec![];
+    let mut cleared = 0;
+    let began = Instant::now();
+    for game in 0..games as u64 {
+        environment.reset(seed.wrapping_add(game));
+        agent.reset(seed.wrapping_add(game));
+        loop {
+            let action = agent.act(&environment.game);
+            if environment.step(action).done {
+                break;
+            }
+        }
+        let game = &environment.game;
+        scores.push(game.score);
+        ticks.push(game.ticks - start.ticks);
+        if game.won {
+            cleared += 1;
+        }
+    }
+    let elapsed = began.elapsed().as_secs_f64();
+    let total_ticks: u64 = ticks.iter().map(|ticks| *ticks as u64).sum();
+    Results {
+        agent: String::from(name),
+        games,
+        mean_score: mean(&scores),
+        median_score: median(&scores),
+        max_score: scores.iter().cloned().max().unwrap_or(0),
+        mean_ticks: mean(&ticks),
+        median_ticks: median(&ticks),
+        clear_rate: if games == 0 {
+            0.0
+        } else {
+            cleared as f64 / games as f64
+        },
+        ticks_per_second: if elapsed > 0.0 {
+            total_ticks as f64 / elapsed
+        } else {
+            0.0
+        },
+    }
+}
+
+#[test]
+fn test_comparing_agents() {
+    use crate::agent::by_name;
+    let level = crate::level::Level::parse(crate::level::TERMINAL).unwrap();
+    let start = level.start(0);
+    let mut random = by_name("random").unwrap();
+    let random_results = run("random", random.as_mut(), &start, 4, 10);
+    let again = run("random", random.as_mut(), &start, 4, 10);
+    assert_eq!(random_results.games, 4);
+    assert_eq!(random_results.max_score, again.max_score);
+    assert_eq!(
+        random_results.median_ticks as u32,
+        again.median_ticks as u32
+    );
+
+    let mut autopilot = by_name("autopilot").unwrap();
+    let autopilot_results = run("autopilot", autopilot.as_mut(), &start, 2, 10);
+    assert!(autopilot_results.mean_score > random_results.mean_score);
+    assert!(by_name("nobody").is_err());
+}
+
+#[test]
+fn test_median() {
+    assert_eq!(median(&[3, 1, 2]) as u32, 2);
+    assert_eq!((median(&[4, 1, 2, 3]) * 2.0) as u32, 5);
+}